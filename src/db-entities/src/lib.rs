@@ -3,6 +3,11 @@
 pub mod prelude;
 
 pub mod activities;
+pub mod events;
 pub mod heart_rate;
+pub mod imu_samples;
 pub mod packets;
+pub mod ppg_samples;
 pub mod sleep_cycles;
+pub mod spo2_readings;
+pub mod sync_sessions;
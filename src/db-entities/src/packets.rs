@@ -10,6 +10,8 @@ pub struct Model {
     pub uuid: Uuid,
     #[sea_orm(column_type = "Binary(1)")]
     pub bytes: Vec<u8>,
+    pub time: Option<DateTime>,
+    pub direction: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
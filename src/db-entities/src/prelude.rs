@@ -1,6 +1,11 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::activities::Entity as Activities;
+pub use super::events::Entity as Events;
 pub use super::heart_rate::Entity as HeartRate;
+pub use super::imu_samples::Entity as ImuSamples;
 pub use super::packets::Entity as Packets;
+pub use super::ppg_samples::Entity as PpgSamples;
 pub use super::sleep_cycles::Entity as SleepCycles;
+pub use super::spo2_readings::Entity as Spo2Readings;
+pub use super::sync_sessions::Entity as SyncSessions;
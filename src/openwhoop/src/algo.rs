@@ -4,5 +4,14 @@ pub use activity::ActivityPeriod;
 pub(crate) mod sleep;
 pub use sleep::SleepCycle;
 
+pub(crate) mod ppg;
+pub use ppg::{PpgAgreement, PpgHeartRate};
+
+pub(crate) mod spo2;
+pub use spo2::{SpO2Calibration, SpO2Config, SpO2Reading};
+
+pub(crate) mod skin_temp;
+pub use skin_temp::SkinTemperature;
+
 pub(crate) mod sleep_consistency;
 pub use sleep_consistency::SleepConsistencyAnalyzer;
@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDateTime, TimeDelta, Timelike};
+use whoop::{ParsedHistoryReading, ParsedPpgSample};
+
+const LOW_CUTOFF_HZ: f64 = 0.5;
+const HIGH_CUTOFF_HZ: f64 = 4.0;
+/// Filter needs some time to settle, peaks found before that are ignored
+const SETTLE_TIME: f64 = 2.0;
+/// Shortest allowed time between two beats (~180 bpm)
+const REFRACTORY_PERIOD: f64 = 0.33;
+/// Peak must be at least this fraction of the highest peak around it
+const PEAK_THRESHOLD: f64 = 0.5;
+const MIN_RR: u16 = 300;
+const MAX_RR: u16 = 2000;
+/// Samples further apart than this start a new segment
+const MAX_GAP: TimeDelta = TimeDelta::seconds(1);
+
+/// Heart rate for one second, estimated from raw green PPG channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PpgHeartRate {
+    pub time: NaiveDateTime,
+    pub bpm: u8,
+    pub rr: Vec<u16>,
+}
+
+/// Comparison of [`PpgHeartRate`] with readings computed by strap
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PpgAgreement {
+    /// Seconds where both strap and PPG estimate have heart rate
+    pub compared: usize,
+    pub mean_abs_error: f64,
+    pub max_abs_error: u8,
+    /// Fraction of compared seconds where difference is at most 5 bpm
+    pub within_5_bpm: f64,
+    /// Compared seconds where strap didn't report any RR interval
+    pub missing_rr: usize,
+    /// Of those, seconds where PPG estimate has RR intervals
+    pub fillable_rr: usize,
+}
+
+impl PpgHeartRate {
+    /// Band-pass filters green channel, detects systolic peaks
+    /// and groups beat-to-beat intervals by second
+    pub fn detect(samples: &[ParsedPpgSample]) -> Vec<PpgHeartRate> {
+        let mut per_second: BTreeMap<NaiveDateTime, Vec<u16>> = BTreeMap::new();
+
+        for segment in segments(samples) {
+            let beats = Self::detect_beats(segment);
+            for beat in beats.windows(2) {
+                let rr = (beat[1] - beat[0]).num_milliseconds();
+                let Ok(rr) = u16::try_from(rr) else {
+                    continue;
+                };
+
+                if (MIN_RR..=MAX_RR).contains(&rr) {
+                    let second = beat[1].with_nanosecond(0).unwrap_or(beat[1]);
+                    per_second.entry(second).or_default().push(rr);
+                }
+            }
+        }
+
+        per_second
+            .into_iter()
+            .map(|(time, rr)| {
+                let mean = rr.iter().map(|&rr| f64::from(rr)).sum::<f64>() / rr.len() as f64;
+                PpgHeartRate {
+                    time,
+                    bpm: (60_000.0 / mean).round() as u8,
+                    rr,
+                }
+            })
+            .collect()
+    }
+
+    /// Sets RR intervals of readings where strap didn't report any, returns indexes of changed readings
+    pub fn fill_missing_rr(
+        estimates: &[PpgHeartRate],
+        history: &mut [ParsedHistoryReading],
+    ) -> Vec<usize> {
+        let estimates = Self::by_time(estimates);
+        let mut filled = Vec::new();
+
+        for (i, reading) in history.iter_mut().enumerate() {
+            if !Self::is_rr_missing(reading) {
+                continue;
+            }
+
+            if let Some(estimate) = estimates.get(&reading.time) {
+                reading.rr = estimate.rr.clone();
+                filled.push(i);
+            }
+        }
+
+        filled
+    }
+
+    /// Stored readings without RR intervals are parsed as `[0]`
+    fn is_rr_missing(reading: &ParsedHistoryReading) -> bool {
+        reading.rr.iter().all(|&rr| rr == 0)
+    }
+
+    fn by_time(estimates: &[PpgHeartRate]) -> BTreeMap<NaiveDateTime, &PpgHeartRate> {
+        estimates.iter().map(|e| (e.time, e)).collect()
+    }
+
+    fn detect_beats(segment: &[ParsedPpgSample]) -> Vec<NaiveDateTime> {
+        let Some(first) = segment.first() else {
+            return Vec::new();
+        };
+
+        let fs = first.sample_rate;
+        if fs <= HIGH_CUTOFF_HZ * 2.0 {
+            return Vec::new();
+        }
+
+        // Blood absorbs more light during systole, so systolic peaks are minimums of raw signal
+        let raw = segment
+            .iter()
+            .map(|s| -f64::from(s.sample.green))
+            .collect::<Vec<_>>();
+        let filtered = band_pass(&raw, fs, LOW_CUTOFF_HZ, HIGH_CUTOFF_HZ);
+
+        Self::find_peaks(&filtered, fs)
+            .into_iter()
+            .map(|i| segment[i].time)
+            .collect()
+    }
+
+    fn find_peaks(signal: &[f64], fs: f64) -> Vec<usize> {
+        let settle = (SETTLE_TIME * fs) as usize;
+        let refractory = (REFRACTORY_PERIOD * fs) as usize;
+        let window = fs as usize;
+
+        let mut peaks: Vec<usize> = Vec::new();
+        for i in settle.max(1)..signal.len().saturating_sub(1) {
+            let value = signal[i];
+            if value <= 0.0 || value <= signal[i - 1] || value < signal[i + 1] {
+                continue;
+            }
+
+            let from = i.saturating_sub(window).max(settle);
+            let to = (i + window).min(signal.len());
+            let local_max = signal[from..to].iter().copied().fold(f64::MIN, f64::max);
+            if value < local_max * PEAK_THRESHOLD {
+                continue;
+            }
+
+            match peaks.last_mut() {
+                Some(last) if i - *last < refractory => {
+                    if value > signal[*last] {
+                        *last = i;
+                    }
+                }
+                _ => peaks.push(i),
+            }
+        }
+
+        peaks
+    }
+}
+
+impl PpgAgreement {
+    pub fn compare(estimates: &[PpgHeartRate], history: &[ParsedHistoryReading]) -> Self {
+        let estimates = PpgHeartRate::by_time(estimates);
+
+        let mut report = Self::default();
+        let mut total_error = 0u64;
+        let mut within = 0usize;
+
+        for reading in history {
+            let Some(estimate) = estimates.get(&reading.time) else {
+                continue;
+            };
+
+            let error = reading.bpm.abs_diff(estimate.bpm);
+            report.compared += 1;
+            report.max_abs_error = report.max_abs_error.max(error);
+            total_error += u64::from(error);
+            if error <= 5 {
+                within += 1;
+            }
+
+            if PpgHeartRate::is_rr_missing(reading) {
+                report.missing_rr += 1;
+                if !estimate.rr.is_empty() {
+                    report.fillable_rr += 1;
+                }
+            }
+        }
+
+        if report.compared > 0 {
+            report.mean_abs_error = total_error as f64 / report.compared as f64;
+            report.within_5_bpm = within as f64 / report.compared as f64;
+        }
+
+        report
+    }
+}
+
+/// Splits samples into continuous runs with same sample rate, where time only goes forward
+pub(crate) fn segments(samples: &[ParsedPpgSample]) -> impl Iterator<Item = &[ParsedPpgSample]> {
+    samples.chunk_by(|a, b| {
+        b.time > a.time && b.time - a.time <= MAX_GAP && a.sample_rate == b.sample_rate
+    })
+}
+
+/// Second order high-pass followed by second order low-pass
+pub(crate) fn band_pass(signal: &[f64], fs: f64, low: f64, high: f64) -> Vec<f64> {
+    let mut high_pass = Biquad::high_pass(fs, low);
+    let mut low_pass = Biquad::low_pass(fs, high);
+    signal
+        .iter()
+        .map(|&x| low_pass.process(high_pass.process(x)))
+        .collect()
+}
+
+/// RBJ audio EQ cookbook biquad with Q = 1/sqrt(2)
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+    initialized: bool,
+}
+
+impl Biquad {
+    fn new(fs: f64, cutoff: f64, high_pass: bool) -> Self {
+        let w0 = 2.0 * std::f64::consts::PI * cutoff / fs;
+        let alpha = w0.sin() / std::f64::consts::SQRT_2;
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+
+        let b = if high_pass {
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0]
+        } else {
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0]
+        };
+
+        Self {
+            b: b.map(|b| b / a0),
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+            x: [0.0; 2],
+            y: [0.0; 2],
+            initialized: false,
+        }
+    }
+
+    fn high_pass(fs: f64, cutoff: f64) -> Self {
+        Self::new(fs, cutoff, true)
+    }
+
+    fn low_pass(fs: f64, cutoff: f64) -> Self {
+        Self::new(fs, cutoff, false)
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        // Start from steady state of first value, raw ADC values are far from zero
+        if !self.initialized {
+            self.x = [x; 2];
+            let gain = self.b.iter().sum::<f64>() / (1.0 + self.a[0] + self.a[1]);
+            self.y = [x * gain; 2];
+            self.initialized = true;
+        }
+
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use whoop::PpgSample;
+
+    use super::*;
+
+    fn synthetic(bpm: f64, seconds: usize, fs: f64) -> Vec<ParsedPpgSample> {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let beat_hz = bpm / 60.0;
+
+        (0..(seconds as f64 * fs) as usize)
+            .map(|i| {
+                let t = i as f64 / fs;
+                let pulse = (2.0 * std::f64::consts::PI * beat_hz * t).sin();
+                let drift = 2000.0 * (2.0 * std::f64::consts::PI * 0.05 * t).sin();
+                let green = (100_000.0 - 500.0 * pulse + drift) as u32;
+
+                ParsedPpgSample {
+                    time: start + TimeDelta::microseconds((t * 1e6) as i64),
+                    sample_rate: fs,
+                    sample: PpgSample {
+                        green,
+                        red: 0,
+                        infrared: 0,
+                        ambient: 0,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detect_heart_rate() {
+        let estimates = PpgHeartRate::detect(&synthetic(72.0, 30, 25.0));
+        assert!(estimates.len() > 20);
+
+        for estimate in estimates {
+            assert!(estimate.bpm.abs_diff(72) <= 3, "{:?}", estimate);
+            for rr in estimate.rr {
+                assert!(rr.abs_diff(833) <= 60, "{}", rr);
+            }
+        }
+    }
+
+    #[test]
+    fn compare_and_fill() {
+        let estimates = PpgHeartRate::detect(&synthetic(60.0, 20, 25.0));
+        let mut history = estimates
+            .iter()
+            .map(|e| ParsedHistoryReading {
+                time: e.time,
+                bpm: 62,
+                rr: vec![0],
+                activity: whoop::Activity::Inactive,
+            })
+            .collect::<Vec<_>>();
+
+        let report = PpgAgreement::compare(&estimates, &history);
+        assert_eq!(report.compared, estimates.len());
+        assert_eq!(report.missing_rr, estimates.len());
+        assert!(report.mean_abs_error <= 3.0);
+
+        let filled = PpgHeartRate::fill_missing_rr(&estimates, &mut history);
+        assert_eq!(filled.len(), estimates.len());
+        assert!(history.iter().all(|h| !PpgHeartRate::is_rr_missing(h)));
+    }
+
+    #[test]
+    fn split_when_time_goes_back() {
+        let mut samples = synthetic(60.0, 2, 25.0);
+        // Second batch of same second starting over at its start
+        samples.extend(synthetic(60.0, 1, 25.0));
+
+        let lengths = segments(&samples).map(<[_]>::len).collect::<Vec<_>>();
+        assert_eq!(lengths, [50, 25]);
+    }
+}
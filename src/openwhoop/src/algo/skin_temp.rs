@@ -0,0 +1,31 @@
+/// Number of previous nights used as personal baseline
+pub const BASELINE_NIGHTS: usize = 14;
+/// Deviation isn't calculated until there are at least this many previous nights
+pub const MIN_BASELINE_NIGHTS: usize = 3;
+
+/// Nightly skin temperature and its deviation from rolling personal baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkinTemperature {
+    /// Mean skin temperature during sleep in °C
+    pub nightly: f64,
+    /// Difference from mean of previous [`BASELINE_NIGHTS`] nights in °C
+    pub deviation: Option<f64>,
+}
+
+impl SkinTemperature {
+    /// `readings` are temperatures measured during sleep,
+    /// `previous` are nightly temperatures of earlier sleeps, oldest first
+    pub fn calculate(readings: &[f64], previous: &[f64]) -> Option<Self> {
+        if readings.is_empty() {
+            return None;
+        }
+
+        let nightly = readings.iter().sum::<f64>() / readings.len() as f64;
+
+        let baseline = &previous[previous.len().saturating_sub(BASELINE_NIGHTS)..];
+        let deviation = (baseline.len() >= MIN_BASELINE_NIGHTS)
+            .then(|| nightly - baseline.iter().sum::<f64>() / baseline.len() as f64);
+
+        Some(Self { nightly, deviation })
+    }
+}
@@ -5,9 +5,9 @@ use whoop::ParsedHistoryReading;
 
 use crate::DatabaseHandler;
 
-use super::ActivityPeriod;
+use super::{ActivityPeriod, SkinTemperature, SpO2Reading};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SleepCycle {
     pub id: NaiveDate,
     pub start: NaiveDateTime,
@@ -18,6 +18,10 @@ pub struct SleepCycle {
     pub min_hrv: u16,
     pub max_hrv: u16,
     pub avg_hrv: u16,
+    pub min_spo2: Option<u8>,
+    pub avg_spo2: Option<u8>,
+    pub skin_temp: Option<f64>,
+    pub skin_temp_deviation: Option<f64>,
 }
 
 impl SleepCycle {
@@ -57,6 +61,44 @@ impl SleepCycle {
             min_hrv,
             max_hrv,
             avg_hrv,
+            min_spo2: None,
+            avg_spo2: None,
+            skin_temp: None,
+            skin_temp_deviation: None,
+        }
+    }
+
+    pub fn with_skin_temp(self, skin_temp: Option<SkinTemperature>) -> Self {
+        Self {
+            skin_temp: skin_temp.map(|t| t.nightly),
+            skin_temp_deviation: skin_temp.and_then(|t| t.deviation),
+            ..self
+        }
+    }
+
+    /// Sets SpO2 summary from readings inside of sleep
+    pub fn with_spo2(self, spo2: &[SpO2Reading]) -> Self {
+        let spo2 = spo2
+            .iter()
+            .filter(|s| s.time >= self.start && s.time <= self.end)
+            .map(|s| s.spo2)
+            .collect::<Vec<_>>();
+
+        if spo2.is_empty() {
+            return Self {
+                min_spo2: None,
+                avg_spo2: None,
+                ..self
+            };
+        }
+
+        let min_spo2 = spo2.iter().copied().fold(f64::MAX, f64::min);
+        let avg_spo2 = spo2.iter().sum::<f64>() / spo2.len() as f64;
+
+        Self {
+            min_spo2: Some(min_spo2.round() as u8),
+            avg_spo2: Some(avg_spo2.round() as u8),
+            ..self
         }
     }
 
@@ -108,6 +150,10 @@ impl From<sleep_cycles::Model> for SleepCycle {
             min_hrv: value.min_hrv.try_into().unwrap(),
             max_hrv: value.max_hrv.try_into().unwrap(),
             avg_hrv: value.avg_hrv.try_into().unwrap(),
+            min_spo2: value.min_spo2.and_then(|spo2| spo2.try_into().ok()),
+            avg_spo2: value.avg_spo2.and_then(|spo2| spo2.try_into().ok()),
+            skin_temp: value.skin_temp,
+            skin_temp_deviation: value.skin_temp_deviation,
         }
     }
 }
@@ -0,0 +1,230 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use whoop::ParsedPpgSample;
+
+use super::ppg::{band_pass, segments};
+
+const LOW_CUTOFF_HZ: f64 = 0.5;
+const HIGH_CUTOFF_HZ: f64 = 4.0;
+
+/// `SpO2 = a + b * R + c * R^2`, where `R` is ratio of ratios
+#[derive(Debug, Clone, Copy)]
+pub struct SpO2Calibration {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl Default for SpO2Calibration {
+    /// Commonly used empirical linear approximation
+    fn default() -> Self {
+        Self {
+            a: 110.0,
+            b: -25.0,
+            c: 0.0,
+        }
+    }
+}
+
+impl SpO2Calibration {
+    pub fn spo2(&self, ratio: f64) -> f64 {
+        self.a + self.b * ratio + self.c * ratio * ratio
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpO2Config {
+    pub calibration: SpO2Calibration,
+    /// Length of window one estimate is computed from
+    pub window: TimeDelta,
+    /// Allowed range of infrared perfusion index (AC / DC),
+    /// outside of it sensor is most likely off wrist or saturated
+    pub min_perfusion: f64,
+    pub max_perfusion: f64,
+    /// Minimal correlation of pulsatile red and infrared signals,
+    /// motion artifacts affect channels differently and lower it
+    pub min_correlation: f64,
+}
+
+impl Default for SpO2Config {
+    fn default() -> Self {
+        Self {
+            calibration: SpO2Calibration::default(),
+            window: TimeDelta::seconds(8),
+            min_perfusion: 0.0005,
+            max_perfusion: 0.2,
+            min_correlation: 0.8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpO2Reading {
+    /// Start of window
+    pub time: NaiveDateTime,
+    /// Oxygen saturation in percent
+    pub spo2: f64,
+    /// Ratio of ratios `(AC_red / DC_red) / (AC_ir / DC_ir)`
+    pub ratio: f64,
+    /// Correlation of pulsatile red and infrared signals
+    pub quality: f64,
+}
+
+impl SpO2Reading {
+    /// Estimates SpO2 from red and infrared channels for every window that passes signal quality gates
+    pub fn detect(samples: &[ParsedPpgSample], config: &SpO2Config) -> Vec<SpO2Reading> {
+        segments(samples)
+            .flat_map(|segment| Self::detect_segment(segment, config))
+            .collect()
+    }
+
+    fn detect_segment(segment: &[ParsedPpgSample], config: &SpO2Config) -> Vec<SpO2Reading> {
+        let Some(first) = segment.first() else {
+            return Vec::new();
+        };
+
+        let fs = first.sample_rate;
+        let window = (config.window.num_milliseconds() as f64 / 1000.0 * fs) as usize;
+        if fs <= HIGH_CUTOFF_HZ * 2.0 || window < 2 {
+            return Vec::new();
+        }
+
+        let red = segment
+            .iter()
+            .map(|s| f64::from(s.sample.red))
+            .collect::<Vec<_>>();
+        let infrared = segment
+            .iter()
+            .map(|s| f64::from(s.sample.infrared))
+            .collect::<Vec<_>>();
+        let red_ac = band_pass(&red, fs, LOW_CUTOFF_HZ, HIGH_CUTOFF_HZ);
+        let infrared_ac = band_pass(&infrared, fs, LOW_CUTOFF_HZ, HIGH_CUTOFF_HZ);
+
+        // First window is skipped, filters need time to settle
+        (1..segment.len() / window)
+            .filter_map(|w| {
+                let range = w * window..(w + 1) * window;
+                Self::estimate(
+                    segment[range.start].time,
+                    (&red[range.clone()], &red_ac[range.clone()]),
+                    (&infrared[range.clone()], &infrared_ac[range]),
+                    config,
+                )
+            })
+            .collect()
+    }
+
+    fn estimate(
+        time: NaiveDateTime,
+        (red, red_ac): (&[f64], &[f64]),
+        (infrared, infrared_ac): (&[f64], &[f64]),
+        config: &SpO2Config,
+    ) -> Option<SpO2Reading> {
+        let red_dc = mean(red);
+        let infrared_dc = mean(infrared);
+        if red_dc <= 0.0 || infrared_dc <= 0.0 {
+            return None;
+        }
+
+        let red_perfusion = rms(red_ac) / red_dc;
+        let infrared_perfusion = rms(infrared_ac) / infrared_dc;
+        if !(config.min_perfusion..=config.max_perfusion).contains(&infrared_perfusion) {
+            return None;
+        }
+
+        let quality = correlation(red_ac, infrared_ac);
+        if quality < config.min_correlation {
+            return None;
+        }
+
+        let ratio = red_perfusion / infrared_perfusion;
+        let spo2 = config.calibration.spo2(ratio);
+        if !(50.0..=100.0).contains(&spo2) {
+            return None;
+        }
+
+        Some(SpO2Reading {
+            time,
+            spo2,
+            ratio,
+            quality,
+        })
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn rms(values: &[f64]) -> f64 {
+    (values.iter().map(|v| v * v).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (a, b) in a.iter().zip(b) {
+        cov += (a - mean_a) * (b - mean_b);
+        var_a += (a - mean_a).powi(2);
+        var_b += (b - mean_b).powi(2);
+    }
+
+    if var_a == 0.0 || var_b == 0.0 {
+        return 0.0;
+    }
+
+    cov / (var_a * var_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use whoop::PpgSample;
+
+    use super::*;
+
+    fn synthetic(ratio: f64, seconds: usize, fs: f64) -> Vec<ParsedPpgSample> {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        (0..(seconds as f64 * fs) as usize)
+            .map(|i| {
+                let t = i as f64 / fs;
+                let pulse = (2.0 * std::f64::consts::PI * 1.2 * t).sin();
+                let infrared = 200_000.0 * (1.0 - 0.01 * pulse);
+                let red = 100_000.0 * (1.0 - 0.01 * ratio * pulse);
+
+                ParsedPpgSample {
+                    time: start + TimeDelta::microseconds((t * 1e6) as i64),
+                    sample_rate: fs,
+                    sample: PpgSample {
+                        green: 0,
+                        red: red as u32,
+                        infrared: infrared as u32,
+                        ambient: 0,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detect_spo2() {
+        let config = SpO2Config::default();
+        let readings = SpO2Reading::detect(&synthetic(0.5, 60, 25.0), &config);
+        assert!(readings.len() >= 5);
+
+        for reading in readings {
+            assert!((reading.ratio - 0.5).abs() < 0.02, "{:?}", reading);
+            assert!((reading.spo2 - 97.5).abs() < 0.5, "{:?}", reading);
+        }
+    }
+
+    #[test]
+    fn flat_signal_is_rejected() {
+        let config = SpO2Config::default();
+        let readings = SpO2Reading::detect(&synthetic(0.0, 60, 25.0), &config);
+        assert!(readings.is_empty());
+    }
+}
@@ -0,0 +1,487 @@
+//! Reader and writer of btsnoop HCI logs, format Android writes to `btsnoop_hci.log`
+//!
+//! File starts with `["btsnoop\0"][version: u32 = 1][datalink: u32]`, followed by records
+//! `[original_len: u32][included_len: u32][flags: u32][drops: u32][timestamp: i64][data..]`.
+//! All integers are big endian, timestamp is in microseconds since 0000-01-01.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Local, NaiveDateTime};
+use uuid::Uuid;
+use whoop::{
+    constants::{
+        PacketType, CMD_FROM_STRAP, CMD_TO_STRAP, DATA_FROM_STRAP, EVENTS_FROM_STRAP, MEMFAULT,
+    },
+    WhoopPacket,
+};
+
+use crate::types::packets::PacketDirection;
+
+const MAGIC: &[u8; 8] = b"btsnoop\0";
+const HEADER_LEN: usize = 16;
+const RECORD_HEADER_LEN: usize = 24;
+/// Microseconds between 0000-01-01 and unix epoch
+const EPOCH_OFFSET: i64 = 0x00dc_ddb3_0f2f_8000;
+
+/// HCI packet without H4 packet type byte
+const DATALINK_HCI: u32 = 1001;
+/// HCI packet prefixed with H4 packet type byte
+const DATALINK_H4: u32 = 1002;
+pub(crate) const H4_ACL: u8 = 0x02;
+
+/// Record flag, set for packets received by host (sent by controller)
+const FLAG_RECEIVED: u32 = 0x01;
+/// Record flag, set for HCI commands and events
+const FLAG_COMMAND_OR_EVENT: u32 = 0x02;
+
+/// ACL packet boundary flag of continuing fragment
+const ACL_CONTINUATION: u16 = 0b01;
+pub(crate) const L2CAP_ATT: u16 = 0x0004;
+
+pub(crate) const ATT_READ_BY_TYPE_RESPONSE: u8 = 0x09;
+const ATT_WRITE_REQUEST: u8 = 0x12;
+pub(crate) const ATT_WRITE_COMMAND: u8 = 0x52;
+pub(crate) const ATT_NOTIFICATION: u8 = 0x1b;
+const ATT_INDICATION: u8 = 0x1d;
+
+pub(crate) const WHOOP_CHARACTERISTICS: [Uuid; 5] = [
+    DATA_FROM_STRAP,
+    CMD_TO_STRAP,
+    CMD_FROM_STRAP,
+    EVENTS_FROM_STRAP,
+    MEMFAULT,
+];
+
+/// One record of btsnoop file, `data` is ACL data packet without H4 type byte
+#[derive(Debug, Clone)]
+pub struct BtsnoopRecord {
+    pub time: NaiveDateTime,
+    /// `true` for packets received from remote device
+    pub received: bool,
+    pub data: Vec<u8>,
+}
+
+/// Value written to or notified on WHOOP characteristic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    pub uuid: Uuid,
+    pub value: Vec<u8>,
+    pub time: NaiveDateTime,
+    pub direction: PacketDirection,
+}
+
+/// Result of [`extract_whoop_packets`]
+#[derive(Debug, Default)]
+pub struct Extracted {
+    pub packets: Vec<CapturedPacket>,
+    /// ATT handles that were mapped to WHOOP characteristics from GATT discovery in capture
+    pub discovered: HashMap<u16, Uuid>,
+    /// ATT handles that were mapped by content of their first WHOOP frame
+    pub inferred: HashMap<u16, Uuid>,
+    /// ATT values on handles that couldn't be mapped
+    pub skipped: usize,
+}
+
+/// Parses ACL data records, HCI commands and events are skipped.
+/// Truncated last record is ignored, file may still be written to
+pub fn parse_records(data: &[u8]) -> anyhow::Result<Vec<BtsnoopRecord>> {
+    let header = data
+        .get(..HEADER_LEN)
+        .ok_or(anyhow!("File is too short for btsnoop header"))?;
+    if &header[..8] != MAGIC {
+        bail!("Not a btsnoop file");
+    }
+
+    let datalink = u32::from_be_bytes([header[12], header[13], header[14], header[15]]);
+    if datalink != DATALINK_HCI && datalink != DATALINK_H4 {
+        bail!("Unsupported btsnoop datalink: {}", datalink);
+    }
+
+    let mut records = Vec::new();
+    let mut rest = &data[HEADER_LEN..];
+    while rest.len() >= RECORD_HEADER_LEN {
+        let be_u32 = |offset: usize| {
+            u32::from_be_bytes([
+                rest[offset],
+                rest[offset + 1],
+                rest[offset + 2],
+                rest[offset + 3],
+            ])
+        };
+
+        let included = be_u32(4) as usize;
+        let flags = be_u32(8);
+        let timestamp = i64::from_be_bytes(rest[16..24].try_into()?);
+        let Some(packet) = rest.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + included) else {
+            warn!("Ignoring truncated btsnoop record");
+            break;
+        };
+        rest = &rest[RECORD_HEADER_LEN + included..];
+
+        let acl = match datalink {
+            DATALINK_H4 => packet
+                .split_first()
+                .filter(|(packet_type, _)| **packet_type == H4_ACL)
+                .map(|(_, acl)| acl),
+            _ => (flags & FLAG_COMMAND_OR_EVENT == 0).then_some(packet),
+        };
+
+        let Some(acl) = acl else {
+            continue;
+        };
+
+        let time = DateTime::from_timestamp_micros(timestamp - EPOCH_OFFSET)
+            .ok_or(anyhow!("Invalid btsnoop timestamp: {}", timestamp))?
+            .with_timezone(&Local)
+            .naive_local();
+
+        records.push(BtsnoopRecord {
+            time,
+            received: flags & FLAG_RECEIVED != 0,
+            data: acl.to_vec(),
+        });
+    }
+
+    Ok(records)
+}
+
+/// Writes records as btsnoop file with H4 datalink, inverse of [`parse_records`]
+pub fn write_records(records: &[BtsnoopRecord]) -> Vec<u8> {
+    let mut file = MAGIC.to_vec();
+    file.extend_from_slice(&1u32.to_be_bytes());
+    file.extend_from_slice(&DATALINK_H4.to_be_bytes());
+
+    for record in records {
+        let length = (record.data.len() as u32 + 1).to_be_bytes();
+        let timestamp = unix_micros(record.time);
+
+        file.extend_from_slice(&length);
+        file.extend_from_slice(&length);
+        file.extend_from_slice(&u32::from(record.received).to_be_bytes());
+        file.extend_from_slice(&0u32.to_be_bytes());
+        file.extend_from_slice(&(timestamp + EPOCH_OFFSET).to_be_bytes());
+        file.push(H4_ACL);
+        file.extend_from_slice(&record.data);
+    }
+
+    file
+}
+
+/// Microseconds since unix epoch of local time, times skipped by DST are taken as UTC
+pub(crate) fn unix_micros(time: NaiveDateTime) -> i64 {
+    time.and_local_timezone(Local)
+        .earliest()
+        .map(|time| time.timestamp_micros())
+        .unwrap_or(time.and_utc().timestamp_micros())
+}
+
+/// ATT PDU reassembled from ACL fragments
+struct AttPdu {
+    time: NaiveDateTime,
+    opcode: u8,
+    params: Vec<u8>,
+}
+
+/// Reassembles L2CAP frames on ATT channel, fragments are tracked per connection and direction
+fn att_pdus(records: &[BtsnoopRecord]) -> Vec<AttPdu> {
+    let mut fragments: HashMap<(u16, bool), (NaiveDateTime, Vec<u8>)> = HashMap::new();
+    let mut pdus = Vec::new();
+
+    for record in records {
+        let Some((header, payload)) = record.data.split_first_chunk::<4>() else {
+            continue;
+        };
+
+        let handle_flags = u16::from_le_bytes([header[0], header[1]]);
+        let connection = handle_flags & 0x0fff;
+        let boundary = (handle_flags >> 12) & 0b11;
+        let key = (connection, record.received);
+
+        if boundary == ACL_CONTINUATION {
+            match fragments.get_mut(&key) {
+                Some((_, frame)) => frame.extend_from_slice(payload),
+                None => continue,
+            }
+        } else {
+            fragments.insert(key, (record.time, payload.to_vec()));
+        }
+
+        let Some((time, frame)) = fragments.get(&key) else {
+            continue;
+        };
+        let Some((l2cap, att)) = frame.split_first_chunk::<4>() else {
+            continue;
+        };
+
+        let length = usize::from(u16::from_le_bytes([l2cap[0], l2cap[1]]));
+        if att.len() < length {
+            continue;
+        }
+
+        let channel = u16::from_le_bytes([l2cap[2], l2cap[3]]);
+        if channel == L2CAP_ATT {
+            if let Some((&opcode, params)) = att[..length].split_first() {
+                pdus.push(AttPdu {
+                    time: *time,
+                    opcode,
+                    params: params.to_vec(),
+                });
+            }
+        }
+
+        fragments.remove(&key);
+    }
+
+    pdus
+}
+
+/// Characteristic declarations from `Read By Type` responses,
+/// entry is `[handle: u16][properties: u8][value_handle: u16][uuid: 2 or 16 bytes]`
+fn discovered_handles(pdus: &[AttPdu]) -> HashMap<u16, Uuid> {
+    let mut handles = HashMap::new();
+
+    for pdu in pdus
+        .iter()
+        .filter(|pdu| pdu.opcode == ATT_READ_BY_TYPE_RESPONSE)
+    {
+        let Some((&entry_len, entries)) = pdu.params.split_first() else {
+            continue;
+        };
+
+        // Only declarations with 128-bit uuid can be WHOOP characteristics
+        if entry_len != 21 {
+            continue;
+        }
+
+        for entry in entries.chunks_exact(21) {
+            let value_handle = u16::from_le_bytes([entry[3], entry[4]]);
+            let mut uuid: [u8; 16] = entry[5..21].try_into().expect("Entry has 21 bytes");
+            uuid.reverse();
+            let uuid = Uuid::from_bytes(uuid);
+
+            if WHOOP_CHARACTERISTICS.contains(&uuid) {
+                handles.insert(value_handle, uuid);
+            }
+        }
+    }
+
+    handles
+}
+
+/// Characteristic of value that is a whole WHOOP frame
+fn infer_characteristic(opcode: u8, value: &[u8]) -> Option<Uuid> {
+    let packet = WhoopPacket::from_data(value).ok()?;
+    let uuid = match (opcode, packet.packet_type) {
+        (ATT_WRITE_COMMAND | ATT_WRITE_REQUEST, PacketType::Command) => CMD_TO_STRAP,
+        (ATT_WRITE_COMMAND | ATT_WRITE_REQUEST, _) => return None,
+        (_, PacketType::CommandResponse) => CMD_FROM_STRAP,
+        (_, PacketType::Event) => EVENTS_FROM_STRAP,
+        _ => DATA_FROM_STRAP,
+    };
+
+    Some(uuid)
+}
+
+/// Extracts writes and notifications on WHOOP characteristics.
+///
+/// Android caches GATT database, so capture doesn't always contain discovery.
+/// Handles that weren't discovered are mapped by first value on them that is a whole WHOOP frame,
+/// `MEMFAULT` doesn't carry WHOOP frames so it is only found through discovery
+pub fn extract_whoop_packets(records: &[BtsnoopRecord]) -> Extracted {
+    let pdus = att_pdus(records);
+    let discovered = discovered_handles(&pdus);
+
+    let values = pdus
+        .into_iter()
+        .filter_map(|pdu| {
+            let direction = match pdu.opcode {
+                ATT_NOTIFICATION | ATT_INDICATION => PacketDirection::FromStrap,
+                ATT_WRITE_COMMAND | ATT_WRITE_REQUEST => PacketDirection::ToStrap,
+                _ => return None,
+            };
+
+            let (handle, value) = pdu.params.split_first_chunk::<2>()?;
+            Some((
+                u16::from_le_bytes(*handle),
+                pdu.opcode,
+                direction,
+                pdu.time,
+                value.to_vec(),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    let mut inferred = HashMap::new();
+    for (handle, opcode, _, _, value) in &values {
+        if discovered.contains_key(handle) || inferred.contains_key(handle) {
+            continue;
+        }
+
+        if let Some(uuid) = infer_characteristic(*opcode, value) {
+            inferred.insert(*handle, uuid);
+        }
+    }
+
+    let mut extracted = Extracted {
+        discovered,
+        inferred,
+        ..Default::default()
+    };
+
+    for (handle, _, direction, time, value) in values {
+        let uuid = extracted
+            .discovered
+            .get(&handle)
+            .or(extracted.inferred.get(&handle));
+
+        match uuid {
+            Some(&uuid) if PacketDirection::of(uuid) == direction => {
+                extracted.packets.push(CapturedPacket {
+                    uuid,
+                    value,
+                    time,
+                    direction,
+                })
+            }
+            _ => extracted.skipped += 1,
+        }
+    }
+
+    extracted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTIFICATION: &str = "aa1c00ab31370268ae7667702d32000000c7b6000010000000000000e01eba47";
+
+    fn header() -> Vec<u8> {
+        let mut file = MAGIC.to_vec();
+        file.extend_from_slice(&1u32.to_be_bytes());
+        file.extend_from_slice(&DATALINK_H4.to_be_bytes());
+        file
+    }
+
+    fn record(file: &mut Vec<u8>, received: bool, micros: i64, acl: &[u8]) {
+        let mut packet = vec![H4_ACL];
+        packet.extend_from_slice(acl);
+
+        file.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+        file.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+        file.extend_from_slice(&u32::from(received).to_be_bytes());
+        file.extend_from_slice(&0u32.to_be_bytes());
+        file.extend_from_slice(&(EPOCH_OFFSET + micros).to_be_bytes());
+        file.extend_from_slice(&packet);
+    }
+
+    /// ACL fragments of ATT PDU on connection `0x0040`, `split` bytes go to first fragment
+    fn acl(att: &[u8], split: usize) -> Vec<Vec<u8>> {
+        let mut l2cap = (att.len() as u16).to_le_bytes().to_vec();
+        l2cap.extend_from_slice(&L2CAP_ATT.to_le_bytes());
+        l2cap.extend_from_slice(att);
+
+        let split = split.min(l2cap.len());
+        [(0x2040u16, &l2cap[..split]), (0x1040, &l2cap[split..])]
+            .into_iter()
+            .filter(|(_, fragment)| !fragment.is_empty())
+            .map(|(handle, fragment)| {
+                let mut acl = handle.to_le_bytes().to_vec();
+                acl.extend_from_slice(&(fragment.len() as u16).to_le_bytes());
+                acl.extend_from_slice(fragment);
+                acl
+            })
+            .collect()
+    }
+
+    fn att(opcode: u8, handle: u16, value: &[u8]) -> Vec<u8> {
+        let mut att = vec![opcode];
+        att.extend_from_slice(&handle.to_le_bytes());
+        att.extend_from_slice(value);
+        att
+    }
+
+    #[test]
+    fn extract_with_discovery() {
+        let mut file = header();
+
+        // Declaration of DATA_FROM_STRAP with value handle 0x0015
+        let mut uuid = DATA_FROM_STRAP.into_bytes();
+        uuid.reverse();
+        let mut discovery = vec![ATT_READ_BY_TYPE_RESPONSE, 21, 0x14, 0x00, 0x10, 0x15, 0x00];
+        discovery.extend_from_slice(&uuid);
+        for acl in acl(&discovery, usize::MAX) {
+            record(&mut file, true, 0, &acl);
+        }
+
+        let value = hex::decode(NOTIFICATION).expect("Invalid hex");
+        for acl in acl(&att(ATT_NOTIFICATION, 0x15, &value), 10) {
+            record(&mut file, true, 1_000_000, &acl);
+        }
+
+        // Truncated record at the end is ignored
+        file.extend_from_slice(&[0, 0, 0, 50]);
+
+        let records = parse_records(&file).expect("Invalid btsnoop");
+        assert_eq!(records.len(), 3);
+
+        let extracted = extract_whoop_packets(&records);
+        assert_eq!(extracted.discovered.get(&0x15), Some(&DATA_FROM_STRAP));
+        assert_eq!(extracted.packets.len(), 1);
+        assert_eq!(extracted.packets[0].uuid, DATA_FROM_STRAP);
+        assert_eq!(extracted.packets[0].value, value);
+        assert_eq!(extracted.packets[0].direction, PacketDirection::FromStrap);
+        assert_eq!(extracted.packets[0].time, records[1].time);
+    }
+
+    #[test]
+    fn infer_handles_without_discovery() {
+        let mut file = header();
+
+        let command = WhoopPacket::get_clock().framed_packet();
+        for acl in acl(&att(ATT_WRITE_COMMAND, 0x12, &command), usize::MAX) {
+            record(&mut file, false, 0, &acl);
+        }
+
+        // Continuation of a frame can't be used to infer handle, but is kept once handle is known
+        for acl in acl(&att(ATT_NOTIFICATION, 0x15, &[0x01, 0x02]), usize::MAX) {
+            record(&mut file, true, 1, &acl);
+        }
+        let value = hex::decode(NOTIFICATION).expect("Invalid hex");
+        for acl in acl(&att(ATT_NOTIFICATION, 0x15, &value), usize::MAX) {
+            record(&mut file, true, 2, &acl);
+        }
+
+        // Unknown handle
+        for acl in acl(&att(ATT_NOTIFICATION, 0x30, &[0x00]), usize::MAX) {
+            record(&mut file, true, 3, &acl);
+        }
+
+        let records = parse_records(&file).expect("Invalid btsnoop");
+        let extracted = extract_whoop_packets(&records);
+
+        assert_eq!(extracted.inferred.get(&0x12), Some(&CMD_TO_STRAP));
+        assert_eq!(extracted.inferred.get(&0x15), Some(&DATA_FROM_STRAP));
+        assert_eq!(extracted.skipped, 1);
+
+        let directions = extracted
+            .packets
+            .iter()
+            .map(|packet| (packet.uuid, packet.direction))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            directions,
+            vec![
+                (CMD_TO_STRAP, PacketDirection::ToStrap),
+                (DATA_FROM_STRAP, PacketDirection::FromStrap),
+                (DATA_FROM_STRAP, PacketDirection::FromStrap),
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_other_files() {
+        assert!(parse_records(b"not a btsnoop file").is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Export of stored packets as captures that can be opened in Wireshark.
+//!
+//! Packets are wrapped in synthetic ATT framing on a single connection, capture starts with
+//! `Read By Type` response declaring WHOOP characteristics, so handles are resolved to uuids
+//! same as in captures of the official app.
+
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use chrono::{Local, NaiveDateTime};
+use db_entities::packets;
+use uuid::Uuid;
+use whoop::constants::CMD_TO_STRAP;
+
+use crate::{
+    btsnoop::{
+        self, unix_micros, BtsnoopRecord, CapturedPacket, ATT_NOTIFICATION,
+        ATT_READ_BY_TYPE_RESPONSE, ATT_WRITE_COMMAND, H4_ACL, L2CAP_ATT, WHOOP_CHARACTERISTICS,
+    },
+    types::packets::PacketDirection,
+};
+
+/// ACL connection handle of synthetic connection
+const CONNECTION: u16 = 0x0040;
+/// Packet boundary flag of first automatically flushable fragment
+const ACL_FIRST: u16 = 0x2000;
+/// Handle of first characteristic declaration, every characteristic takes three handles:
+/// declaration, value and client configuration descriptor
+const FIRST_HANDLE: u16 = 0x0010;
+
+const PROPERTY_WRITE_WITHOUT_RESPONSE: u8 = 0x04;
+const PROPERTY_WRITE: u8 = 0x08;
+const PROPERTY_NOTIFY: u8 = 0x10;
+
+/// `LINKTYPE_BLUETOOTH_HCI_H4_WITH_PHDR`, H4 packet prefixed with big endian direction
+const LINKTYPE_H4_WITH_PHDR: u16 = 201;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    Btsnoop,
+    Pcapng,
+}
+
+impl CaptureFormat {
+    /// Format implied by file extension, btsnoop unless it is `.pcapng`
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("pcapng") => Self::Pcapng,
+            _ => Self::Btsnoop,
+        }
+    }
+}
+
+impl Display for CaptureFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            CaptureFormat::Btsnoop => "btsnoop",
+            CaptureFormat::Pcapng => "pcapng",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for CaptureFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "btsnoop" => Ok(CaptureFormat::Btsnoop),
+            "pcapng" => Ok(CaptureFormat::Pcapng),
+            _ => Err(format!("Unknown capture format: {}", s)),
+        }
+    }
+}
+
+/// Converts stored packets, direction of packets stored before it was recorded is implied from
+/// characteristic. Packets without time get time of previous packet, or of first packet with it
+pub fn stored_packets(models: Vec<packets::Model>) -> Vec<CapturedPacket> {
+    let first_time = models
+        .iter()
+        .find_map(|model| model.time)
+        .unwrap_or_else(|| Local::now().naive_local());
+
+    models
+        .into_iter()
+        .scan(first_time, |previous, model| {
+            let time = model.time.unwrap_or(*previous);
+            *previous = time;
+
+            let direction = model
+                .direction
+                .and_then(|direction| direction.parse().ok())
+                .unwrap_or(PacketDirection::of(model.uuid));
+
+            Some(CapturedPacket {
+                uuid: model.uuid,
+                value: model.bytes,
+                time,
+                direction,
+            })
+        })
+        .collect()
+}
+
+/// Value handle of characteristic in synthetic capture
+fn value_handle(uuid: Uuid) -> Option<u16> {
+    WHOOP_CHARACTERISTICS
+        .iter()
+        .position(|characteristic| *characteristic == uuid)
+        .map(|index| FIRST_HANDLE + 3 * index as u16 + 1)
+}
+
+fn acl_record(time: NaiveDateTime, received: bool, att: &[u8]) -> BtsnoopRecord {
+    let mut data = (ACL_FIRST | CONNECTION).to_le_bytes().to_vec();
+    data.extend_from_slice(&(att.len() as u16 + 4).to_le_bytes());
+    data.extend_from_slice(&(att.len() as u16).to_le_bytes());
+    data.extend_from_slice(&L2CAP_ATT.to_le_bytes());
+    data.extend_from_slice(att);
+
+    BtsnoopRecord {
+        time,
+        received,
+        data,
+    }
+}
+
+/// `Read By Type` response with declarations of all WHOOP characteristics,
+/// entry is `[handle: u16][properties: u8][value_handle: u16][uuid: 16 bytes]`
+fn discovery(time: NaiveDateTime) -> BtsnoopRecord {
+    let mut att = vec![ATT_READ_BY_TYPE_RESPONSE, 21];
+    for uuid in WHOOP_CHARACTERISTICS {
+        let value_handle = value_handle(uuid).expect("WHOOP characteristic");
+        let properties = match uuid {
+            CMD_TO_STRAP => PROPERTY_WRITE | PROPERTY_WRITE_WITHOUT_RESPONSE,
+            _ => PROPERTY_NOTIFY,
+        };
+
+        let mut uuid = uuid.into_bytes();
+        uuid.reverse();
+
+        att.extend_from_slice(&(value_handle - 1).to_le_bytes());
+        att.push(properties);
+        att.extend_from_slice(&value_handle.to_le_bytes());
+        att.extend_from_slice(&uuid);
+    }
+
+    acl_record(time, true, &att)
+}
+
+/// Frames packets as ATT writes and notifications, packets on other characteristics are skipped
+pub fn synthetic_records(packets: &[CapturedPacket]) -> Vec<BtsnoopRecord> {
+    let Some(first) = packets.first() else {
+        return Vec::new();
+    };
+
+    let mut records = vec![discovery(first.time)];
+    for packet in packets {
+        let Some(handle) = value_handle(packet.uuid) else {
+            warn!("Skipping packet on unknown characteristic: {}", packet.uuid);
+            continue;
+        };
+
+        let (opcode, received) = match packet.direction {
+            PacketDirection::FromStrap => (ATT_NOTIFICATION, true),
+            PacketDirection::ToStrap => (ATT_WRITE_COMMAND, false),
+        };
+
+        let mut att = vec![opcode];
+        att.extend_from_slice(&handle.to_le_bytes());
+        att.extend_from_slice(&packet.value);
+        records.push(acl_record(packet.time, received, &att));
+    }
+
+    records
+}
+
+/// Pcapng with single interface of `LINKTYPE_BLUETOOTH_HCI_H4_WITH_PHDR`, little endian
+fn write_pcapng(records: &[BtsnoopRecord]) -> Vec<u8> {
+    fn block(file: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+        let length = (12 + body.len()) as u32;
+        file.extend_from_slice(&block_type.to_le_bytes());
+        file.extend_from_slice(&length.to_le_bytes());
+        file.extend_from_slice(body);
+        file.extend_from_slice(&length.to_le_bytes());
+    }
+
+    let mut file = Vec::new();
+
+    let mut section = PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes().to_vec();
+    section.extend_from_slice(&1u16.to_le_bytes());
+    section.extend_from_slice(&0u16.to_le_bytes());
+    // Section length isn't specified
+    section.extend_from_slice(&(-1i64).to_le_bytes());
+    block(&mut file, PCAPNG_SECTION_HEADER, &section);
+
+    // Default timestamp resolution is microseconds, snap length 0 means no limit
+    let mut interface = LINKTYPE_H4_WITH_PHDR.to_le_bytes().to_vec();
+    interface.extend_from_slice(&0u16.to_le_bytes());
+    interface.extend_from_slice(&0u32.to_le_bytes());
+    block(&mut file, PCAPNG_INTERFACE_DESCRIPTION, &interface);
+
+    for record in records {
+        let mut packet = u32::from(record.received).to_be_bytes().to_vec();
+        packet.push(H4_ACL);
+        packet.extend_from_slice(&record.data);
+
+        let timestamp = unix_micros(record.time) as u64;
+        let mut body = 0u32.to_le_bytes().to_vec();
+        body.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&packet);
+        body.resize(body.len().next_multiple_of(4), 0);
+        block(&mut file, PCAPNG_ENHANCED_PACKET, &body);
+    }
+
+    file
+}
+
+/// Capture file of packets in given format
+pub fn export(packets: &[CapturedPacket], format: CaptureFormat) -> Vec<u8> {
+    let records = synthetic_records(packets);
+    match format {
+        CaptureFormat::Btsnoop => btsnoop::write_records(&records),
+        CaptureFormat::Pcapng => write_pcapng(&records),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use whoop::{
+        constants::{DATA_FROM_STRAP, MEMFAULT},
+        WhoopPacket,
+    };
+
+    use super::*;
+
+    fn packets() -> Vec<CapturedPacket> {
+        let time = NaiveDateTime::parse_from_str("2025-01-02 15:19:04", "%Y-%m-%d %H:%M:%S")
+            .expect("Invalid time");
+
+        vec![
+            CapturedPacket {
+                uuid: CMD_TO_STRAP,
+                value: WhoopPacket::get_clock().framed_packet(),
+                time,
+                direction: PacketDirection::ToStrap,
+            },
+            CapturedPacket {
+                uuid: DATA_FROM_STRAP,
+                value: hex::decode(
+                    "aa1c00ab31370268ae7667702d32000000c7b6000010000000000000e01eba47",
+                )
+                .expect("Invalid hex"),
+                time: time + chrono::Duration::milliseconds(250),
+                direction: PacketDirection::FromStrap,
+            },
+            CapturedPacket {
+                uuid: MEMFAULT,
+                value: vec![0x01, 0x02, 0x03],
+                time: time + chrono::Duration::seconds(1),
+                direction: PacketDirection::FromStrap,
+            },
+        ]
+    }
+
+    #[test]
+    fn btsnoop_round_trip() {
+        let packets = packets();
+        let file = export(&packets, CaptureFormat::Btsnoop);
+
+        let records = btsnoop::parse_records(&file).expect("Invalid btsnoop");
+        let extracted = btsnoop::extract_whoop_packets(&records);
+
+        assert_eq!(extracted.discovered.len(), WHOOP_CHARACTERISTICS.len());
+        assert!(extracted.inferred.is_empty());
+        assert_eq!(extracted.skipped, 0);
+        assert_eq!(extracted.packets, packets);
+    }
+
+    #[test]
+    fn pcapng_blocks() {
+        let file = export(&packets(), CaptureFormat::Pcapng);
+
+        let mut blocks = Vec::new();
+        let mut rest = file.as_slice();
+        while !rest.is_empty() {
+            let block_type = u32::from_le_bytes(rest[0..4].try_into().unwrap());
+            let length = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+            assert_eq!(length % 4, 0);
+            assert_eq!(rest[length - 4..length], rest[4..8]);
+
+            blocks.push(block_type);
+            rest = &rest[length..];
+        }
+
+        // Discovery and three packets
+        assert_eq!(
+            blocks,
+            vec![
+                PCAPNG_SECTION_HEADER,
+                PCAPNG_INTERFACE_DESCRIPTION,
+                PCAPNG_ENHANCED_PACKET,
+                PCAPNG_ENHANCED_PACKET,
+                PCAPNG_ENHANCED_PACKET,
+                PCAPNG_ENHANCED_PACKET,
+            ]
+        );
+    }
+
+    #[test]
+    fn stored_packets_without_time_and_direction() {
+        let time = NaiveDateTime::parse_from_str("2025-01-02 15:19:04", "%Y-%m-%d %H:%M:%S")
+            .expect("Invalid time");
+        let model = |id, uuid, time| packets::Model {
+            id,
+            uuid,
+            bytes: vec![],
+            time,
+            direction: None,
+        };
+
+        let packets = stored_packets(vec![
+            model(1, CMD_TO_STRAP, None),
+            model(2, DATA_FROM_STRAP, Some(time)),
+            model(3, DATA_FROM_STRAP, None),
+        ]);
+
+        assert!(packets.iter().all(|packet| packet.time == time));
+        assert_eq!(packets[0].direction, PacketDirection::ToStrap);
+        assert_eq!(packets[2].direction, PacketDirection::FromStrap);
+    }
+}
@@ -0,0 +1,234 @@
+//! Background syncing, strap is looked for on schedule and history is synced whenever it is in range
+
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::{Local, NaiveDateTime};
+use serde::Serialize;
+use tokio::time::{sleep, timeout};
+use whoop::WhoopPacket;
+
+use crate::{
+    transport::WhoopTransport, DatabaseHandler, HistoryAck, OpenWhoop, ReconnectPolicy, WhoopDevice,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonState {
+    Scanning,
+    Syncing,
+    /// Sleeps and events are detected in synced history
+    Analyzing,
+    /// Waiting for next scan
+    Waiting,
+    Stopped,
+}
+
+/// Written as JSON to status file on every state change
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonStatus {
+    pub state: DaemonState,
+    pub pid: u32,
+    pub updated_at: NaiveDateTime,
+    /// When strap was last found in range
+    pub last_seen: Option<NaiveDateTime>,
+    /// When last sync, including analysis, finished without error
+    pub last_sync: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+    /// Syncs finished without error since daemon started
+    pub syncs: u32,
+    pub failed_syncs: u32,
+    pub next_scan: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DaemonConfig {
+    /// Time from end of one scan or sync to start of next scan
+    pub interval: Duration,
+    /// Strap is considered out of range if it isn't found in this time
+    pub scan_timeout: Duration,
+    pub reconnect_policy: ReconnectPolicy,
+    pub history_ack: HistoryAck,
+    /// See [`WhoopDevice::with_resume`]
+    pub resume: bool,
+    pub status_file: Option<PathBuf>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(15 * 60),
+            scan_timeout: Duration::from_secs(30),
+            reconnect_policy: ReconnectPolicy::default(),
+            history_ack: HistoryAck::default(),
+            resume: true,
+            status_file: None,
+        }
+    }
+}
+
+pub struct Daemon {
+    db: DatabaseHandler,
+    config: DaemonConfig,
+    status: DaemonStatus,
+}
+
+impl Daemon {
+    pub fn new(db: DatabaseHandler, config: DaemonConfig) -> Self {
+        Self {
+            db,
+            config,
+            status: DaemonStatus {
+                state: DaemonState::Scanning,
+                pid: std::process::id(),
+                updated_at: Local::now().naive_local(),
+                last_seen: None,
+                last_sync: None,
+                last_error: None,
+                syncs: 0,
+                failed_syncs: 0,
+                next_scan: None,
+            },
+        }
+    }
+
+    pub fn status(&self) -> &DaemonStatus {
+        &self.status
+    }
+
+    /// Syncs history every time `find_strap` finds strap, until `shutdown` completes.
+    ///
+    /// `find_strap` is expected to scan until strap is found, it is cancelled after scan timeout.
+    /// Unless resume is disabled in config, sync interrupted by shutdown is continued from its
+    /// last checkpoint by next sync. Failed sync, including panic while analyzing, is recorded in
+    /// status and daemon keeps running
+    pub async fn run<T, F, Fut>(
+        &mut self,
+        mut find_strap: F,
+        shutdown: impl Future<Output = ()>,
+    ) -> anyhow::Result<()>
+    where
+        T: WhoopTransport,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        tokio::pin!(shutdown);
+
+        loop {
+            self.status.next_scan = None;
+            self.update(DaemonState::Scanning)?;
+
+            let found = tokio::select! {
+                _ = &mut shutdown => break,
+                found = timeout(self.config.scan_timeout, find_strap()) => found,
+            };
+
+            match found {
+                Err(_) => info!("Strap not found in {:?}", self.config.scan_timeout),
+                Ok(Err(error)) => self.record_error(error),
+                Ok(Ok(transport)) => {
+                    self.status.last_seen = Some(Local::now().naive_local());
+                    match self.sync(transport, shutdown.as_mut()).await {
+                        Ok(true) => {
+                            self.status.syncs += 1;
+                            self.status.last_sync = Some(Local::now().naive_local());
+                            self.status.last_error = None;
+                        }
+                        Ok(false) => break,
+                        Err(error) => {
+                            self.status.failed_syncs += 1;
+                            self.record_error(error);
+                        }
+                    }
+                }
+            }
+
+            self.status.next_scan = Some(Local::now().naive_local() + self.config.interval);
+            self.update(DaemonState::Waiting)?;
+
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = sleep(self.config.interval) => {}
+            }
+        }
+
+        info!("Daemon stopped");
+        self.status.next_scan = None;
+        self.update(DaemonState::Stopped)
+    }
+
+    /// Returns whether sync finished, `false` if it was interrupted by shutdown
+    async fn sync<T: WhoopTransport>(
+        &mut self,
+        transport: T,
+        shutdown: Pin<&mut impl Future<Output = ()>>,
+    ) -> anyhow::Result<bool> {
+        let mut device = WhoopDevice::with_transport(transport, self.db.clone())
+            .with_reconnect_policy(self.config.reconnect_policy)
+            .with_history_ack(self.config.history_ack)
+            .with_resume(self.config.resume);
+
+        self.update(DaemonState::Syncing)?;
+        device.connect().await?;
+        device.initialize().await?;
+
+        let result = tokio::select! {
+            _ = shutdown => None,
+            result = device.sync_history() => Some(result),
+        };
+
+        if device.is_connected().await.unwrap_or_default() {
+            device
+                .send_command(WhoopPacket::exit_high_freq_sync())
+                .await?;
+        }
+
+        let Some(result) = result else {
+            info!("Sync interrupted by shutdown");
+            return Ok(false);
+        };
+        result?;
+
+        self.update(DaemonState::Analyzing)?;
+        // Analysis runs in its own task, so panic in it fails only this sync
+        let whoop = OpenWhoop::new(self.db.clone());
+        tokio::spawn(async move {
+            whoop.detect_sleeps().await?;
+            whoop.detect_events().await
+        })
+        .await
+        .context("Analysis failed")??;
+
+        Ok(true)
+    }
+
+    fn record_error(&mut self, error: anyhow::Error) {
+        error!("{:#}", error);
+        self.status.last_error = Some(format!("{:#}", error));
+    }
+
+    fn update(&mut self, state: DaemonState) -> anyhow::Result<()> {
+        self.status.state = state;
+        self.status.updated_at = Local::now().naive_local();
+
+        match &self.config.status_file {
+            Some(path) => write_status(path, &self.status),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Status is written to temporary file first, so readers never see partially written status
+fn write_status(path: &Path, status: &DaemonStatus) -> anyhow::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    std::fs::write(&temporary, serde_json::to_vec_pretty(status)?)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
@@ -1,17 +1,27 @@
 use chrono::{Local, NaiveDateTime, TimeZone};
-use db_entities::{packets, sleep_cycles};
+use db_entities::{events, imu_samples, packets, ppg_samples, sleep_cycles};
 use migration::{Migrator, MigratorTrait, OnConflict};
 use sea_orm::{
-    prelude::Expr, ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, Condition, Database,
-    DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, Database, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
 };
 use uuid::Uuid;
 
 mod history;
 pub use history::SearchHistory;
-use whoop::constants::DATA_FROM_STRAP;
 
-use crate::algo::SleepCycle;
+mod stored_packets;
+pub use stored_packets::SearchPackets;
+
+mod ppg;
+mod sync_sessions;
+
+use whoop::{
+    constants::{DATA_FROM_STRAP, EVENTS_FROM_STRAP},
+    ImuData, RawOpticalData, RealtimeHeartRate, StrapEvent,
+};
+
+use crate::{algo::SleepCycle, btsnoop::CapturedPacket, types::packets::PacketDirection};
 
 #[derive(Clone)]
 pub struct DatabaseHandler {
@@ -31,6 +41,12 @@ impl DatabaseHandler {
         Self { db }
     }
 
+    /// Whether no query is running, every connection of pool is idle
+    pub fn is_idle(&self) -> bool {
+        let pool = self.db.get_sqlite_connection_pool();
+        pool.num_idle() == pool.size() as usize
+    }
+
     pub async fn create_packet(
         &self,
         char: Uuid,
@@ -40,6 +56,8 @@ impl DatabaseHandler {
             id: NotSet,
             uuid: Set(char),
             bytes: Set(data),
+            time: Set(Some(Local::now().naive_local())),
+            direction: Set(Some(PacketDirection::of(char).to_string())),
         };
 
         let packet = packet.insert(&self.db).await?;
@@ -52,9 +70,10 @@ impl DatabaseHandler {
         bpm: u8,
         rr: Vec<u16>,
         activity: i64,
+        skin_temp: Option<f64>,
     ) -> anyhow::Result<()> {
         let time = timestamp_to_local(unix);
-        info!(target: "HistoryReading", "time: {}, bpm: {}", time, bpm);
+        debug!(target: "HistoryReading", "time: {}, bpm: {}", time, bpm);
 
         let packet = db_entities::heart_rate::ActiveModel {
             id: NotSet,
@@ -62,6 +81,7 @@ impl DatabaseHandler {
             time: Set(time),
             rr_intervals: Set(rr_to_string(rr)),
             activity: Set(Some(activity)),
+            skin_temp: Set(skin_temp),
         };
 
         let _model = db_entities::heart_rate::Entity::insert(packet)
@@ -70,23 +90,165 @@ impl DatabaseHandler {
                     .update_column(db_entities::heart_rate::Column::Bpm)
                     .update_column(db_entities::heart_rate::Column::RrIntervals)
                     .update_column(db_entities::heart_rate::Column::Activity)
+                    .update_column(db_entities::heart_rate::Column::SkinTemp)
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Realtime readings don't have activity, so they never overwrite readings from history
+    pub async fn create_realtime_reading(&self, reading: RealtimeHeartRate) -> anyhow::Result<()> {
+        let model = db_entities::heart_rate::ActiveModel {
+            id: NotSet,
+            bpm: Set(reading.bpm as i16),
+            time: Set(timestamp_to_local(reading.unix)),
+            rr_intervals: Set(rr_to_string(reading.rr)),
+            activity: Set(None),
+            skin_temp: Set(None),
+        };
+
+        let _r = db_entities::heart_rate::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(db_entities::heart_rate::Column::Time)
+                    .do_nothing()
                     .to_owned(),
             )
+            .do_nothing()
             .exec(&self.db)
             .await?;
 
         Ok(())
     }
 
+    pub async fn create_event(&self, event: StrapEvent, raw: Vec<u8>) -> anyhow::Result<()> {
+        let time = timestamp_to_local(event.unix());
+        info!(target: "StrapEvent", "time: {}, event: {}", time, event.name());
+
+        let model = events::ActiveModel {
+            id: NotSet,
+            time: Set(time),
+            event_id: Set(event.event_number().into()),
+            event: Set(event.name()),
+            raw: Set(raw),
+        };
+
+        let _r = events::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([events::Column::Time, events::Column::EventId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Samples are keyed on stored packet frame was decoded from, and position of frame among
+    /// frames decoded from it, so handling same packet again doesn't store them twice
+    pub async fn create_imu_samples(
+        &self,
+        imu: ImuData,
+        packet_id: i32,
+        frame: i16,
+    ) -> anyhow::Result<()> {
+        if imu.samples.is_empty() {
+            return Ok(());
+        }
+
+        let time = timestamp_to_local(imu.unix);
+        let models =
+            imu.samples
+                .into_iter()
+                .enumerate()
+                .map(|(i, sample)| imu_samples::ActiveModel {
+                    id: NotSet,
+                    packet_id: Set(packet_id),
+                    frame: Set(frame),
+                    time: Set(time),
+                    sample: Set(i as i16),
+                    acc_x: Set(sample.accelerometer[0]),
+                    acc_y: Set(sample.accelerometer[1]),
+                    acc_z: Set(sample.accelerometer[2]),
+                    gyr_x: Set(sample.gyroscope[0]),
+                    gyr_y: Set(sample.gyroscope[1]),
+                    gyr_z: Set(sample.gyroscope[2]),
+                });
+
+        let _r = imu_samples::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    imu_samples::Column::PacketId,
+                    imu_samples::Column::Frame,
+                    imu_samples::Column::Sample,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Samples are keyed same as [`DatabaseHandler::create_imu_samples`]
+    pub async fn create_ppg_samples(
+        &self,
+        optical: RawOpticalData,
+        packet_id: i32,
+        frame: i16,
+    ) -> anyhow::Result<()> {
+        if optical.samples.is_empty() {
+            return Ok(());
+        }
+
+        let time = timestamp_to_local(optical.unix);
+        let sample_rate = optical.sample_rate as i16;
+        let models =
+            optical
+                .samples
+                .into_iter()
+                .enumerate()
+                .map(|(i, sample)| ppg_samples::ActiveModel {
+                    id: NotSet,
+                    packet_id: Set(packet_id),
+                    frame: Set(frame),
+                    time: Set(time),
+                    sample: Set(i as i16),
+                    sample_rate: Set(sample_rate),
+                    green: Set(sample.green.into()),
+                    red: Set(sample.red.into()),
+                    infrared: Set(sample.infrared.into()),
+                    ambient: Set(sample.ambient.into()),
+                });
+
+        let _r = ppg_samples::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    ppg_samples::Column::PacketId,
+                    ppg_samples::Column::Frame,
+                    ppg_samples::Column::Sample,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Stored data and event packets after `id`, so both readings and events can be rebuilt
     pub async fn get_packets(&self, id: i32) -> anyhow::Result<Vec<packets::Model>> {
         let stream = packets::Entity::find()
             .filter(packets::Column::Id.gt(id))
-            .filter(packets::Column::Uuid.eq(DATA_FROM_STRAP))
-            .filter(
-                Condition::any()
-                    .add(Expr::cust("LOWER(HEX(bytes))").like("aa6400a1%"))
-                    .add(Expr::cust("LOWER(HEX(bytes))").like("aa5c00f0%")),
-            )
+            .filter(packets::Column::Uuid.is_in([DATA_FROM_STRAP, EVENTS_FROM_STRAP]))
             .order_by_asc(packets::Column::Id)
             .limit(10_000)
             .all(&self.db)
@@ -95,6 +257,43 @@ impl DatabaseHandler {
         Ok(stream)
     }
 
+    /// Stores packets captured outside of openwhoop, returns number of stored packets
+    pub async fn create_captured_packets(
+        &self,
+        packets: &[CapturedPacket],
+    ) -> anyhow::Result<usize> {
+        // Keeps number of bound parameters under SQLite limit
+        for chunk in packets.chunks(1000) {
+            let models = chunk.iter().map(|packet| packets::ActiveModel {
+                id: NotSet,
+                uuid: Set(packet.uuid),
+                bytes: Set(packet.value.clone()),
+                time: Set(Some(packet.time)),
+                direction: Set(Some(packet.direction.to_string())),
+            });
+
+            packets::Entity::insert_many(models).exec(&self.db).await?;
+        }
+
+        Ok(packets.len())
+    }
+
+    /// Packets on all characteristics in order they were received
+    pub async fn get_session_packets(
+        &self,
+        id: i32,
+        limit: Option<u64>,
+    ) -> anyhow::Result<Vec<packets::Model>> {
+        let packets = packets::Entity::find()
+            .filter(packets::Column::Id.gt(id))
+            .order_by_asc(packets::Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+
+        Ok(packets)
+    }
+
     pub async fn get_latest_sleep(
         &self,
     ) -> anyhow::Result<Option<db_entities::sleep_cycles::Model>> {
@@ -118,6 +317,10 @@ impl DatabaseHandler {
             min_hrv: Set(sleep.min_hrv.into()),
             max_hrv: Set(sleep.max_hrv.into()),
             avg_hrv: Set(sleep.max_hrv.into()),
+            min_spo2: Set(sleep.min_spo2.map(i16::from)),
+            avg_spo2: Set(sleep.avg_spo2.map(i16::from)),
+            skin_temp: Set(sleep.skin_temp),
+            skin_temp_deviation: Set(sleep.skin_temp_deviation),
         };
 
         let _r = sleep_cycles::Entity::insert(model)
@@ -132,6 +335,10 @@ impl DatabaseHandler {
                         sleep_cycles::Column::MinHrv,
                         sleep_cycles::Column::MaxHrv,
                         sleep_cycles::Column::AvgHrv,
+                        sleep_cycles::Column::MinSpo2,
+                        sleep_cycles::Column::AvgSpo2,
+                        sleep_cycles::Column::SkinTemp,
+                        sleep_cycles::Column::SkinTempDeviation,
                     ])
                     .to_owned(),
             )
@@ -154,3 +361,148 @@ fn timestamp_to_local(unix: u32) -> NaiveDateTime {
 fn rr_to_string(rr: Vec<u16>) -> String {
     rr.iter().map(u16::to_string).collect::<Vec<_>>().join(",")
 }
+
+#[cfg(test)]
+mod tests {
+    use whoop::{
+        constants::{EventNumber, PacketType, CMD_FROM_STRAP},
+        ImuSample, PpgSample, WhoopPacket,
+    };
+
+    use super::*;
+    use crate::OpenWhoop;
+
+    #[tokio::test]
+    async fn rerun_stored_events() {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let wrist_on = WhoopPacket::new(
+            PacketType::Event,
+            0,
+            EventNumber::WristOn.as_u8(),
+            hex::decode("00b70c54670000").expect("Invalid hex"),
+        );
+        db.create_packet(EVENTS_FROM_STRAP, wrist_on.framed_packet())
+            .await
+            .expect("Unable to store packet");
+        db.create_packet(CMD_FROM_STRAP, vec![0xaa])
+            .await
+            .expect("Unable to store packet");
+
+        let packets = db.get_packets(0).await.expect("Unable to read packets");
+        assert_eq!(packets.len(), 1);
+
+        let mut whoop = OpenWhoop::new(db.clone());
+        for packet in packets {
+            whoop
+                .handle_packet(packet)
+                .await
+                .expect("Unable to handle packet");
+        }
+
+        let events = events::Entity::find()
+            .all(&db.db)
+            .await
+            .expect("Unable to read events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "WristOn");
+    }
+
+    #[tokio::test]
+    async fn imu_batches_in_same_second() {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let imu = ImuData {
+            unix: 1_736_000_000,
+            samples: vec![
+                ImuSample {
+                    accelerometer: [1, 2, 3],
+                    gyroscope: [4, 5, 6],
+                };
+                4
+            ],
+        };
+
+        // Two batches from different packets and two from same packet, all in same second
+        for (packet_id, frame) in [(1, 0), (2, 0), (2, 1)] {
+            db.create_imu_samples(imu.clone(), packet_id, frame)
+                .await
+                .expect("Unable to store samples");
+        }
+        // Same packet handled again
+        db.create_imu_samples(imu.clone(), 1, 0)
+            .await
+            .expect("Unable to store samples");
+
+        let stored = imu_samples::Entity::find()
+            .all(&db.db)
+            .await
+            .expect("Unable to read samples");
+        assert_eq!(stored.len(), 12);
+    }
+
+    #[tokio::test]
+    async fn ppg_batches_in_same_second() {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let optical = |green| RawOpticalData {
+            unix: 1_736_000_000,
+            sample_rate: 25,
+            samples: vec![
+                PpgSample {
+                    green,
+                    red: 0,
+                    infrared: 0,
+                    ambient: 0,
+                };
+                2
+            ],
+        };
+
+        for (green, packet_id) in [(2, 2), (1, 1), (1, 1)] {
+            db.create_ppg_samples(optical(green), packet_id, 0)
+                .await
+                .expect("Unable to store samples");
+        }
+
+        let stored = db
+            .search_ppg(SearchHistory::default())
+            .await
+            .expect("Unable to read samples")
+            .into_iter()
+            .map(|sample| sample.sample.green)
+            .collect::<Vec<_>>();
+        assert_eq!(stored, [1, 1, 2, 2]);
+    }
+
+    #[tokio::test]
+    async fn ppg_time_runs_across_batches() {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let optical = RawOpticalData {
+            unix: 1_736_000_000,
+            sample_rate: 25,
+            samples: vec![
+                PpgSample {
+                    green: 1,
+                    red: 0,
+                    infrared: 0,
+                    ambient: 0,
+                };
+                10
+            ],
+        };
+
+        // Two batches in same second
+        for packet_id in [1, 2] {
+            db.create_ppg_samples(optical.clone(), packet_id, 0)
+                .await
+                .expect("Unable to store samples");
+        }
+
+        let stored = db
+            .search_ppg(SearchHistory::default())
+            .await
+            .expect("Unable to read samples");
+        assert_eq!(stored.len(), 20);
+        assert!(stored.windows(2).all(|pair| pair[1].time > pair[0].time));
+        let span = stored[19].time - stored[0].time;
+        assert_eq!(span.num_milliseconds(), 19 * 40);
+    }
+}
@@ -40,6 +40,23 @@ impl DatabaseHandler {
         Ok(history)
     }
 
+    /// Skin temperatures in °C of readings that have it
+    pub async fn search_skin_temp(&self, options: SearchHistory) -> anyhow::Result<Vec<f64>> {
+        let limit = options.limit;
+        let skin_temp = heart_rate::Entity::find()
+            .filter(options.conditions())
+            .filter(heart_rate::Column::SkinTemp.is_not_null())
+            .limit(limit)
+            .order_by_asc(heart_rate::Column::Time)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .filter_map(|model| model.skin_temp)
+            .collect();
+
+        Ok(skin_temp)
+    }
+
     fn parse_reading(model: heart_rate::Model) -> ParsedHistoryReading {
         ParsedHistoryReading {
             time: model.time,
@@ -0,0 +1,109 @@
+use chrono::TimeDelta;
+use db_entities::{heart_rate, ppg_samples, spo2_readings};
+use migration::OnConflict;
+use sea_orm::{
+    prelude::Expr, ActiveValue::NotSet, ColumnTrait, Condition, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use whoop::{ParsedPpgSample, PpgSample};
+
+use crate::algo::SpO2Reading;
+
+use super::{rr_to_string, DatabaseHandler, SearchHistory};
+
+impl DatabaseHandler {
+    pub async fn search_ppg(&self, options: SearchHistory) -> anyhow::Result<Vec<ParsedPpgSample>> {
+        let conditions = Condition::all()
+            .add_option(options.from.map(|from| ppg_samples::Column::Time.gt(from)))
+            .add_option(options.to.map(|to| ppg_samples::Column::Time.lt(to)));
+
+        let samples = ppg_samples::Entity::find()
+            .filter(conditions)
+            .limit(options.limit)
+            .order_by_asc(ppg_samples::Column::Time)
+            // Strap sends several batches per second, they are kept in order they were received
+            .order_by_asc(ppg_samples::Column::PacketId)
+            .order_by_asc(ppg_samples::Column::Frame)
+            .order_by_asc(ppg_samples::Column::Sample)
+            .all(&self.db)
+            .await?;
+
+        // Every batch restarts sample count, so samples are counted across batches of same second
+        let mut second = None;
+        let mut index = 0;
+        let samples = samples
+            .into_iter()
+            .map(|model| {
+                if second != Some(model.time) {
+                    second = Some(model.time);
+                    index = 0;
+                }
+                let sample = Self::parse_ppg(model, index);
+                index += 1;
+                sample
+            })
+            .collect();
+
+        Ok(samples)
+    }
+
+    /// Replaces RR intervals of stored reading at `time`
+    pub async fn update_rr(&self, time: chrono::NaiveDateTime, rr: Vec<u16>) -> anyhow::Result<()> {
+        heart_rate::Entity::update_many()
+            .col_expr(
+                heart_rate::Column::RrIntervals,
+                Expr::value(rr_to_string(rr)),
+            )
+            .filter(heart_rate::Column::Time.eq(time))
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_spo2(&self, readings: &[SpO2Reading]) -> anyhow::Result<()> {
+        if readings.is_empty() {
+            return Ok(());
+        }
+
+        let models = readings.iter().map(|reading| spo2_readings::ActiveModel {
+            id: NotSet,
+            time: Set(reading.time),
+            spo2: Set(reading.spo2),
+            ratio: Set(reading.ratio),
+            quality: Set(reading.quality),
+        });
+
+        let _r = spo2_readings::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::column(spo2_readings::Column::Time)
+                    .update_columns([
+                        spo2_readings::Column::Spo2,
+                        spo2_readings::Column::Ratio,
+                        spo2_readings::Column::Quality,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// `index` is position of sample among all samples stored for its second
+    fn parse_ppg(model: ppg_samples::Model, index: u32) -> ParsedPpgSample {
+        let sample_rate = f64::from(model.sample_rate.max(1));
+        let offset = TimeDelta::microseconds((f64::from(index) / sample_rate * 1e6) as i64);
+
+        ParsedPpgSample {
+            time: model.time + offset,
+            sample_rate,
+            sample: PpgSample {
+                green: model.green.try_into().unwrap_or_default(),
+                red: model.red.try_into().unwrap_or_default(),
+                infrared: model.infrared.try_into().unwrap_or_default(),
+                ambient: model.ambient.try_into().unwrap_or_default(),
+            },
+        }
+    }
+}
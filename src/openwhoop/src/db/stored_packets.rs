@@ -0,0 +1,47 @@
+use chrono::NaiveDateTime;
+use db_entities::packets;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use uuid::Uuid;
+
+use super::DatabaseHandler;
+
+#[derive(Default)]
+pub struct SearchPackets {
+    /// Only packets with id greater than this
+    pub after_id: Option<i32>,
+    /// Only packets with id less than this
+    pub before_id: Option<i32>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub uuid: Option<Uuid>,
+    pub limit: Option<u64>,
+}
+
+impl SearchPackets {
+    pub(crate) fn conditions(self) -> Condition {
+        Condition::all()
+            .add_option(self.after_id.map(|id| packets::Column::Id.gt(id)))
+            .add_option(self.before_id.map(|id| packets::Column::Id.lt(id)))
+            .add_option(self.from.map(|from| packets::Column::Time.gt(from)))
+            .add_option(self.to.map(|to| packets::Column::Time.lt(to)))
+            .add_option(self.uuid.map(|uuid| packets::Column::Uuid.eq(uuid)))
+    }
+}
+
+impl DatabaseHandler {
+    /// Stored packets in order they were received
+    pub async fn search_packets(
+        &self,
+        options: SearchPackets,
+    ) -> anyhow::Result<Vec<packets::Model>> {
+        let limit = options.limit;
+        let packets = packets::Entity::find()
+            .filter(options.conditions())
+            .order_by_asc(packets::Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+
+        Ok(packets)
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::Local;
+use db_entities::sync_sessions;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+
+use super::DatabaseHandler;
+
+impl DatabaseHandler {
+    pub async fn create_sync_session(&self) -> anyhow::Result<sync_sessions::Model> {
+        let now = Local::now().naive_local();
+        let session = sync_sessions::ActiveModel {
+            id: NotSet,
+            started_at: Set(now),
+            updated_at: Set(now),
+            finished_at: Set(None),
+            last_acknowledged: Set(None),
+            batches: Set(0),
+        };
+
+        Ok(session.insert(&self.db).await?)
+    }
+
+    /// Records that `HistoryEnd` with `data` was acknowledged in session
+    pub async fn update_sync_checkpoint(&self, id: i32, data: u32) -> anyhow::Result<()> {
+        let Some(session) = sync_sessions::Entity::find_by_id(id).one(&self.db).await? else {
+            anyhow::bail!("Sync session {} doesn't exist", id);
+        };
+
+        let batches = session.batches + 1;
+        let mut session: sync_sessions::ActiveModel = session.into();
+        session.updated_at = Set(Local::now().naive_local());
+        session.last_acknowledged = Set(Some(data.into()));
+        session.batches = Set(batches);
+        session.update(&self.db).await?;
+
+        Ok(())
+    }
+
+    /// Marks session as finished, strap reported that all history was sent
+    pub async fn finish_sync_session(&self, id: i32) -> anyhow::Result<()> {
+        let now = Local::now().naive_local();
+        let session = sync_sessions::ActiveModel {
+            id: Set(id),
+            updated_at: Set(now),
+            finished_at: Set(Some(now)),
+            ..Default::default()
+        };
+        session.update(&self.db).await?;
+
+        Ok(())
+    }
+
+    /// Last acknowledged `HistoryEnd` data of most recent session that acknowledged anything
+    pub async fn last_sync_checkpoint(&self) -> anyhow::Result<Option<u32>> {
+        let session = sync_sessions::Entity::find()
+            .filter(sync_sessions::Column::LastAcknowledged.is_not_null())
+            .order_by_desc(sync_sessions::Column::UpdatedAt)
+            .order_by_desc(sync_sessions::Column::Id)
+            .one(&self.db)
+            .await?;
+
+        Ok(session
+            .and_then(|session| session.last_acknowledged)
+            .and_then(|data| u32::try_from(data).ok()))
+    }
+}
@@ -1,57 +1,207 @@
-use std::{collections::BTreeSet, time::Duration};
+use std::{ops::Range, time::Duration};
 
-use btleplug::{
-    api::{CharPropFlags, Characteristic, Peripheral as _, WriteType},
-    platform::Peripheral,
-};
-use futures::StreamExt;
-use tokio::time::sleep;
+use anyhow::anyhow;
+use btleplug::{api::ValueNotification, platform::Peripheral};
+use futures::{Stream, StreamExt};
+use tokio::time::{sleep, timeout};
 use uuid::Uuid;
 use whoop::{
     constants::{
-        CMD_FROM_STRAP, CMD_TO_STRAP, DATA_FROM_STRAP, EVENTS_FROM_STRAP, MEMFAULT, WHOOP_SERVICE,
+        CommandNumber, CMD_FROM_STRAP, CMD_TO_STRAP, DATA_FROM_STRAP, EVENTS_FROM_STRAP, MEMFAULT,
     },
-    WhoopPacket,
+    CommandResponse, FrameDecoder, HistoryDecoder, RealtimeHeartRate, WhoopData, WhoopPacket,
+};
+
+use crate::{
+    openwhoop::OpenWhoop,
+    transport::{BleTransport, WhoopTransport},
+    DatabaseHandler,
 };
 
-use crate::{openwhoop::OpenWhoop, DatabaseHandler};
+mod reconnect;
+pub use reconnect::ReconnectPolicy;
+
+mod progress;
+pub use progress::{ReceivedHistory, SyncProgress};
+
+mod request;
+pub use request::RequestPolicy;
+use request::{PendingRequest, SequenceNumbers};
+
+type ProgressCallback = Box<dyn FnMut(&SyncProgress) + Send + Sync>;
+
+/// How `HistoryEnd` metadata, which ends every batch of history, is answered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryAck {
+    /// Batch is acknowledged with `HistoricalDataResult`, which lets strap trim its records
+    #[default]
+    Acknowledge,
+    /// Batch isn't acknowledged, next one is requested after read pointer is moved past it,
+    /// so strap keeps all records
+    ReadOnly,
+    /// Transmission is aborted with `AbortHistoricalTransmits` after first batch
+    Abort,
+}
 
-pub struct WhoopDevice {
-    peripheral: Peripheral,
+/// Why history download stopped
+enum HistoryOutcome {
+    Complete,
+    Disconnected,
+    /// Notifications ended while transport is still connected, e.g. replay finished
+    Ended,
+    /// Transmission was aborted before strap sent all history
+    Aborted,
+}
+
+pub struct WhoopDevice<T = BleTransport> {
+    transport: T,
     whoop: OpenWhoop,
+    seq: SequenceNumbers,
+    request_policy: RequestPolicy,
+    reconnect_policy: Option<ReconnectPolicy>,
+    history_ack: HistoryAck,
+    /// Whether history sync is resumed from last checkpoint
+    resume: bool,
+    on_progress: Option<ProgressCallback>,
+    /// Progress of history sync, tracked while history is synced if progress is reported
+    sync_progress: Option<SyncProgress>,
+    /// Session acknowledged history batches are recorded in, set while history is synced
+    sync_session: Option<i32>,
+    /// Data of last `HistoryEnd` read pointer was moved past, since history was started
+    last_skipped: Option<u32>,
 }
 
 impl WhoopDevice {
     pub fn new(peripheral: Peripheral, db: DatabaseHandler) -> Self {
+        Self::with_transport(BleTransport::new(peripheral), db)
+    }
+}
+
+impl<T: WhoopTransport> WhoopDevice<T> {
+    pub fn with_transport(transport: T, db: DatabaseHandler) -> Self {
         Self {
-            peripheral,
+            transport,
             whoop: OpenWhoop::new(db),
+            seq: SequenceNumbers::default(),
+            request_policy: RequestPolicy::default(),
+            reconnect_policy: None,
+            history_ack: HistoryAck::default(),
+            resume: true,
+            on_progress: None,
+            sync_progress: None,
+            sync_session: None,
+            last_skipped: None,
         }
     }
 
-    pub async fn connect(&mut self) -> anyhow::Result<()> {
-        self.peripheral.connect().await?;
-        self.peripheral.discover_services().await?;
-        Ok(())
+    pub fn with_request_policy(self, request_policy: RequestPolicy) -> Self {
+        Self {
+            request_policy,
+            ..self
+        }
     }
 
-    pub async fn is_connected(&mut self) -> anyhow::Result<bool> {
-        let is_connected = self.peripheral.is_connected().await?;
-        Ok(is_connected)
+    /// Without reconnect policy [`WhoopDevice::sync_history`] stops when connection drops
+    pub fn with_reconnect_policy(self, reconnect_policy: ReconnectPolicy) -> Self {
+        Self {
+            reconnect_policy: Some(reconnect_policy),
+            ..self
+        }
+    }
+
+    /// See [`OpenWhoop::with_history_decoder`]
+    pub fn with_history_decoder(self, version: u8, decoder: HistoryDecoder) -> Self {
+        Self {
+            whoop: self.whoop.with_history_decoder(version, decoder),
+            ..self
+        }
+    }
+
+    pub fn with_history_ack(self, history_ack: HistoryAck) -> Self {
+        Self {
+            history_ack,
+            ..self
+        }
+    }
+
+    /// Resumes [`WhoopDevice::sync_history`] from last checkpoint, by asking strap for range of
+    /// stored history and moving its read pointer.
+    ///
+    /// Layouts of these commands aren't verified against captures of official app yet,
+    /// if range strap answers with doesn't contain checkpoint, all stored history is synced.
+    /// Resume is on by default
+    pub fn with_resume(self, resume: bool) -> Self {
+        Self { resume, ..self }
     }
 
-    fn create_char(characteristic: Uuid) -> Characteristic {
-        Characteristic {
-            uuid: characteristic,
-            service_uuid: WHOOP_SERVICE,
-            properties: CharPropFlags::empty(),
-            descriptors: BTreeSet::new(),
+    /// `on_progress` is called with progress of [`WhoopDevice::sync_history`], which also asks
+    /// strap for range of stored history to estimate it
+    pub fn with_progress(
+        self,
+        on_progress: impl FnMut(&SyncProgress) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            on_progress: Some(Box::new(on_progress)),
+            ..self
         }
     }
 
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn database(&self) -> &DatabaseHandler {
+        &self.whoop.database
+    }
+
+    pub async fn connect(&mut self) -> anyhow::Result<()> {
+        self.transport.connect().await
+    }
+
+    pub async fn is_connected(&mut self) -> anyhow::Result<bool> {
+        self.transport.is_connected().await
+    }
+
     async fn subscribe(&self, char: Uuid) -> anyhow::Result<()> {
-        self.peripheral.subscribe(&Self::create_char(char)).await?;
-        Ok(())
+        self.transport.subscribe(char).await
+    }
+
+    /// Connects and initializes strap again, waiting longer after every failed attempt.
+    /// Without reconnect policy only one attempt is made
+    pub async fn reconnect(&mut self) -> anyhow::Result<()> {
+        let policy = self.reconnect_policy.unwrap_or(ReconnectPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        });
+
+        for attempt in 0..policy.max_attempts {
+            let delay = policy.delay(attempt);
+            info!(
+                "Reconnecting in {:.1?}, attempt {}/{}",
+                delay,
+                attempt + 1,
+                policy.max_attempts
+            );
+            sleep(delay).await;
+
+            let result = async {
+                self.connect().await?;
+                self.initialize().await
+            };
+
+            match result.await {
+                Ok(()) => {
+                    info!("Reconnected");
+                    return Ok(());
+                }
+                Err(error) => warn!("Reconnect failed: {}", error),
+            }
+        }
+
+        Err(anyhow!(
+            "Unable to reconnect after {} attempts",
+            policy.max_attempts
+        ))
     }
 
     pub async fn initialize(&mut self) -> anyhow::Result<()> {
@@ -70,20 +220,196 @@ impl WhoopDevice {
         Ok(())
     }
 
+    /// Sends command without waiting for response, use [`WhoopDevice::request`] to get it
     pub async fn send_command(&mut self, packet: WhoopPacket) -> anyhow::Result<()> {
+        let packet = packet.with_seq(self.seq.next());
+        self.write_command(&packet).await
+    }
+
+    async fn write_command(&self, packet: &WhoopPacket) -> anyhow::Result<()> {
         let packet = packet.framed_packet();
-        self.peripheral
-            .write(
-                &Self::create_char(CMD_TO_STRAP),
-                &packet,
-                WriteType::WithoutResponse,
-            )
-            .await?;
-        Ok(())
+        self.transport.write(CMD_TO_STRAP, &packet).await
     }
 
+    /// Sends command and waits for strap to respond to it,
+    /// command is resent with new `seq` if response doesn't arrive in time
+    pub async fn request(&mut self, packet: WhoopPacket) -> anyhow::Result<CommandResponse> {
+        let mut notifications = self.transport.notifications().await?;
+        let policy = self.request_policy;
+
+        for attempt in 0..=policy.retries {
+            let packet = packet.clone().with_seq(self.seq.next());
+            let pending = PendingRequest::new(&packet);
+            self.write_command(&packet).await?;
+
+            match timeout(policy.timeout, Self::response(&mut notifications, pending)).await {
+                Ok(response) => return response,
+                Err(_) => warn!(
+                    "No response to command {} (seq: {}), attempt {}/{}",
+                    pending.cmd,
+                    pending.seq,
+                    attempt + 1,
+                    policy.retries + 1
+                ),
+            }
+        }
+
+        Err(anyhow!(
+            "Command {} timed out after {} attempts",
+            packet.cmd,
+            policy.retries + 1
+        ))
+    }
+
+    async fn response(
+        notifications: &mut (impl Stream<Item = ValueNotification> + Unpin),
+        pending: PendingRequest,
+    ) -> anyhow::Result<CommandResponse> {
+        let mut decoder = FrameDecoder::new();
+        while let Some(notification) = notifications.next().await {
+            if notification.uuid != CMD_FROM_STRAP {
+                continue;
+            }
+
+            let response = decoder
+                .decode(&notification.value)
+                .into_iter()
+                .find(|packet| pending.is_response(packet));
+
+            if let Some(response) = response {
+                return Ok(CommandResponse::from_packet(&response));
+            }
+        }
+
+        Err(anyhow!("Whoop disconnected while waiting for response"))
+    }
+
+    /// Downloads history until strap reports it is complete, disconnects or notifications end.
+    ///
+    /// Every acknowledged `HistoryEnd` is stored as checkpoint of sync session, if strap still
+    /// has records after last checkpoint, sync continues from it instead of oldest stored record.
+    /// With reconnect policy, strap is reconnected and sync continues when connection drops.
+    /// Unless history is acknowledged, sync session isn't created
     pub async fn sync_history(&mut self) -> anyhow::Result<()> {
-        let mut notifications = self.peripheral.notifications().await?;
+        self.whoop.take_received_history();
+        self.sync_progress = self.on_progress.is_some().then(SyncProgress::new);
+        let result = self.sync_history_until_done().await;
+        self.sync_progress = None;
+        result
+    }
+
+    async fn sync_history_until_done(&mut self) -> anyhow::Result<()> {
+        let session = match self.history_ack {
+            HistoryAck::Acknowledge => Some(self.whoop.database.create_sync_session().await?),
+            HistoryAck::ReadOnly | HistoryAck::Abort => None,
+        };
+
+        loop {
+            let resumed = self.resume_from_checkpoint().await?;
+            if let (Some(progress), Some((range, start))) = (&mut self.sync_progress, resumed) {
+                progress.range = Some(range);
+                progress.start.get_or_insert(start);
+            }
+            self.report_progress();
+
+            self.sync_session = session.as_ref().map(|session| session.id);
+            let result = self.download_history().await;
+            self.sync_session = None;
+
+            match result? {
+                HistoryOutcome::Complete => {
+                    if let Some(progress) = &mut self.sync_progress {
+                        progress.complete = true;
+                    }
+                    self.report_progress();
+
+                    if let Some(session) = session {
+                        self.whoop.database.finish_sync_session(session.id).await?;
+                    }
+                    return Ok(());
+                }
+                HistoryOutcome::Disconnected if self.reconnect_policy.is_some() => {
+                    self.reconnect().await?;
+                }
+                HistoryOutcome::Disconnected | HistoryOutcome::Ended | HistoryOutcome::Aborted => {
+                    return Ok(())
+                }
+            }
+        }
+    }
+
+    /// Moves read pointer of strap to last checkpoint, if it is inside range strap has stored.
+    ///
+    /// Range is requested only if sync is resumed from checkpoint or progress is reported,
+    /// returns it with position sync starts from
+    async fn resume_from_checkpoint(&mut self) -> anyhow::Result<Option<(Range<u32>, u32)>> {
+        let checkpoint = match self.resume {
+            true => self.whoop.database.last_sync_checkpoint().await?,
+            false => None,
+        };
+        if checkpoint.is_none() && self.sync_progress.is_none() {
+            return Ok(None);
+        }
+
+        let range = match self.request(WhoopPacket::get_data_range()).await {
+            Ok(CommandResponse::DataRange { start, end }) if start <= end => start..end,
+            Ok(response) => {
+                warn!("Unexpected response to data range: {:?}", response);
+                return Ok(None);
+            }
+            Err(error) => {
+                warn!(
+                    "Unable to get data range, syncing all stored history: {}",
+                    error
+                );
+                return Ok(None);
+            }
+        };
+
+        let Some(checkpoint) = checkpoint else {
+            return Ok(Some((range.clone(), range.start)));
+        };
+
+        // Strap already trimmed records up to checkpoint, or was reset since
+        if checkpoint <= range.start || checkpoint > range.end {
+            debug!(
+                "Checkpoint {} is outside of stored range {:?}",
+                checkpoint, range
+            );
+            return Ok(Some((range.clone(), range.start)));
+        }
+
+        info!("Resuming history sync from checkpoint {}", checkpoint);
+        match self
+            .request(WhoopPacket::set_read_pointer(checkpoint))
+            .await
+        {
+            Ok(CommandResponse::Ack {
+                cmd: CommandNumber::SetReadPointer,
+            }) => {}
+            Ok(response) => {
+                warn!(
+                    "Unexpected response to read pointer, syncing all stored history: {:?}",
+                    response
+                );
+                return Ok(Some((range.clone(), range.start)));
+            }
+            Err(error) => {
+                warn!(
+                    "Unable to set read pointer, syncing all stored history: {}",
+                    error
+                );
+                return Ok(Some((range.clone(), range.start)));
+            }
+        }
+
+        Ok(Some((range, checkpoint)))
+    }
+
+    async fn download_history(&mut self) -> anyhow::Result<HistoryOutcome> {
+        let mut notifications = self.transport.notifications().await?;
+        self.whoop.take_history_complete();
+        self.last_skipped = None;
         self.send_command(WhoopPacket::history_start()).await?;
 
         loop {
@@ -94,23 +420,476 @@ impl WhoopDevice {
                 _ = sleep => {
                     if self.on_sleep().await?{
                         error!("Whoop disconnected");
-                        break;
+                        return Ok(HistoryOutcome::Disconnected);
+                    }
+                },
+                notification = notification => {
+                    let Some(notification) = notification else {
+                        warn!("Notifications ended");
+                        return match self.on_sleep().await? {
+                            true => Ok(HistoryOutcome::Disconnected),
+                            false => Ok(HistoryOutcome::Ended),
+                        };
+                    };
+
+                    // Response can't be written once connection drops
+                    let unacknowledged = match self.handle_notification(notification).await {
+                        Ok(unacknowledged) => unacknowledged,
+                        Err(error) => {
+                            if self.on_sleep().await? {
+                                error!("Whoop disconnected: {}", error);
+                                return Ok(HistoryOutcome::Disconnected);
+                            }
+
+                            return Err(error);
+                        }
+                    };
+
+                    if let Some(data) = unacknowledged {
+                        if let Some(outcome) = self.skip_batch(data).await? {
+                            return Ok(outcome);
+                        }
+                    }
+
+                    if self.whoop.take_history_complete() {
+                        info!("History complete");
+                        return Ok(HistoryOutcome::Complete);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Continues history without acknowledging batch that ended with `data`,
+    /// returns outcome if download has to stop.
+    ///
+    /// If strap sends batch that doesn't end after last skipped one, its read pointer didn't move
+    /// and transmission is aborted instead of receiving same batch forever
+    async fn skip_batch(&mut self, data: u32) -> anyhow::Result<Option<HistoryOutcome>> {
+        if self.history_ack == HistoryAck::ReadOnly {
+            if self.last_skipped.is_some_and(|skipped| data <= skipped) {
+                warn!("Read pointer of strap didn't move past {}", data);
+            } else {
+                self.last_skipped = Some(data);
+                match self.request(WhoopPacket::set_read_pointer(data)).await {
+                    Ok(CommandResponse::Ack {
+                        cmd: CommandNumber::SetReadPointer,
+                    }) => {
+                        self.send_command(WhoopPacket::history_start()).await?;
+                        return Ok(None);
+                    }
+                    Ok(response) => {
+                        warn!(
+                            "Unexpected response to read pointer past unacknowledged batch: {:?}",
+                            response
+                        );
+                    }
+                    Err(error) => {
+                        if self.on_sleep().await? {
+                            error!("Whoop disconnected: {}", error);
+                            return Ok(Some(HistoryOutcome::Disconnected));
+                        }
+
+                        warn!(
+                            "Unable to move read pointer past unacknowledged batch: {}",
+                            error
+                        );
+                    }
+                }
+            }
+        }
+
+        info!("Aborting history transmission at {}", data);
+        self.send_command(WhoopPacket::abort_historical_transmits())
+            .await?;
+        Ok(Some(HistoryOutcome::Aborted))
+    }
+
+    /// Streams realtime heart rate until Ctrl-C is pressed or strap disconnects,
+    /// if `store` is set notifications and readings are saved to database
+    pub async fn live_heart_rate(
+        &mut self,
+        store: bool,
+        mut on_reading: impl FnMut(&RealtimeHeartRate),
+    ) -> anyhow::Result<()> {
+        let mut notifications = self.transport.notifications().await?;
+        let mut decoder = FrameDecoder::new();
+        self.send_command(WhoopPacket::toggle_realtime_hr(true))
+            .await?;
+
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            let notification = notifications.next();
+            let sleep = sleep(Duration::from_secs(10));
+
+            tokio::select! {
+                _ = &mut ctrl_c => break,
+                _ = sleep => {
+                    if self.on_sleep().await? {
+                        error!("Whoop disconnected");
+                        return Ok(());
                     }
                 },
                 Some(notification) = notification => {
-                    let packet = self.whoop.store_packet(notification).await?;
-                    if let Some(packet) = self.whoop.handle_packet(packet).await?{
-                        self.send_command(packet).await?;
+                    if notification.uuid != DATA_FROM_STRAP {
+                        continue;
+                    }
+
+                    let packets = decoder.decode(&notification.value);
+                    if store {
+                        self.whoop.store_packet(notification).await?;
+                    }
+
+                    for packet in packets {
+                        let Ok(WhoopData::RealtimeHeartRate(reading)) = WhoopData::from_packet(packet) else {
+                            continue;
+                        };
+
+                        on_reading(&reading);
+                        if store {
+                            self.whoop.database.create_realtime_reading(reading).await?;
+                        }
                     }
                 }
             }
         }
 
-        Ok(())
+        self.send_command(WhoopPacket::toggle_realtime_hr(false))
+            .await
+    }
+
+    /// Enables optical sensor and starts raw PPG collection
+    pub async fn start_raw_capture(&mut self) -> anyhow::Result<()> {
+        self.send_command(WhoopPacket::toggle_optical_mode(true))
+            .await?;
+        self.send_command(WhoopPacket::enable_optical_data(true))
+            .await?;
+        self.send_command(WhoopPacket::start_raw_data()).await
+    }
+
+    pub async fn stop_raw_capture(&mut self) -> anyhow::Result<()> {
+        self.send_command(WhoopPacket::stop_raw_data()).await?;
+        self.send_command(WhoopPacket::enable_optical_data(false))
+            .await?;
+        self.send_command(WhoopPacket::toggle_optical_mode(false))
+            .await
+    }
+
+    /// Captures raw PPG samples for `duration` or until Ctrl-C is pressed,
+    /// all notifications and decoded samples are stored to database
+    pub async fn capture_raw_data(&mut self, duration: Duration) -> anyhow::Result<()> {
+        let mut notifications = self.transport.notifications().await?;
+        self.start_raw_capture().await?;
+
+        let deadline = sleep(duration);
+        tokio::pin!(deadline);
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            let notification = notifications.next();
+            let sleep = sleep(Duration::from_secs(10));
+
+            tokio::select! {
+                _ = &mut deadline => break,
+                _ = &mut ctrl_c => break,
+                _ = sleep => {
+                    if self.on_sleep().await? {
+                        error!("Whoop disconnected");
+                        return Ok(());
+                    }
+                },
+                Some(notification) = notification => {
+                    self.handle_notification(notification).await?;
+                }
+            }
+        }
+
+        self.stop_raw_capture().await
+    }
+
+    /// Stores and handles every notification until stream ends, strap isn't asked for anything.
+    /// Used with [`ReplayTransport`](crate::transport::ReplayTransport), returns number of notifications
+    pub async fn replay_notifications(&mut self) -> anyhow::Result<usize> {
+        let mut notifications = self.transport.notifications().await?;
+        let mut count = 0;
+        while let Some(notification) = notifications.next().await {
+            self.handle_notification(notification).await?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Stores notification and sends responses to it
+    /// Returns data of `HistoryEnd` that wasn't acknowledged because of [`HistoryAck`]
+    async fn handle_notification(
+        &mut self,
+        notification: ValueNotification,
+    ) -> anyhow::Result<Option<u32>> {
+        let mut unacknowledged = None;
+        let mut history_end = None;
+        let packet = self.whoop.store_packet(notification).await?;
+        for packet in self.whoop.handle_packet(packet).await? {
+            let acknowledged = Self::acknowledged_history(&packet);
+            history_end = history_end.or(acknowledged);
+            if acknowledged.is_some() && self.history_ack != HistoryAck::Acknowledge {
+                unacknowledged = acknowledged;
+                continue;
+            }
+
+            self.send_command(packet).await?;
+
+            if let (Some(session), Some(data)) = (self.sync_session, acknowledged) {
+                self.whoop
+                    .database
+                    .update_sync_checkpoint(session, data)
+                    .await?;
+            }
+        }
+
+        if let Some(progress) = &mut self.sync_progress {
+            progress.add_received(self.whoop.take_received_history());
+            if let Some(data) = history_end {
+                progress.batches += 1;
+                progress.position = Some(data);
+            }
+        }
+        if history_end.is_some() {
+            self.report_progress();
+        }
+
+        Ok(unacknowledged)
+    }
+
+    /// Data of `HistoryEnd` that `HistoricalDataResult` command acknowledges
+    fn acknowledged_history(packet: &WhoopPacket) -> Option<u32> {
+        if packet.cmd != CommandNumber::HistoricalDataResult.as_u8() {
+            return None;
+        }
+
+        packet
+            .data
+            .get(1..5)
+            .and_then(|data| data.try_into().ok())
+            .map(u32::from_le_bytes)
+    }
+
+    fn report_progress(&mut self) {
+        if let (Some(progress), Some(on_progress)) =
+            (&mut self.sync_progress, &mut self.on_progress)
+        {
+            progress.update_elapsed();
+            on_progress(progress);
+        }
     }
 
     async fn on_sleep(&mut self) -> anyhow::Result<bool> {
-        let is_connected = self.peripheral.is_connected().await?;
+        let is_connected = self.transport.is_connected().await?;
         Ok(!is_connected)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use whoop::constants::PacketType;
+
+    use crate::{
+        transport::{MemoryStrap, MemoryTransport, RecordedNotification, ReplayTransport},
+        SearchHistory,
+    };
+
+    use super::*;
+
+    async fn device() -> (WhoopDevice<MemoryTransport>, MemoryStrap) {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let (transport, strap) = MemoryTransport::pair();
+        let mut device = WhoopDevice::with_transport(transport, db);
+        device.connect().await.expect("Unable to connect");
+        device.initialize().await.expect("Unable to initialize");
+        (device, strap)
+    }
+
+    async fn next_command(strap: &mut MemoryStrap) -> WhoopPacket {
+        let (characteristic, frame) = strap.next_write().await.expect("Transport dropped");
+        assert_eq!(characteristic, CMD_TO_STRAP);
+        WhoopPacket::from_data(&frame).expect("Invalid command")
+    }
+
+    #[tokio::test]
+    async fn replay_recorded_notifications() {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let start = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .expect("Invalid time");
+
+        let notifications = [
+            "aa5c00f02f0c053f940900da106966280080545401360195040000000000000000a34cff0050bf3b144efb3da4a4463f299c0dbf00004c42144efb3da4a4463f299c0dbff40155023b03530255016004010c020c2000000000000002e8c17c8d",
+            "aa1c00ab31370268ae7667702d32000000c7b6000010000000000000e01eba47",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, packet)| RecordedNotification {
+            uuid: DATA_FROM_STRAP,
+            value: hex::decode(packet).expect("Invalid hex"),
+            time: Some(start + chrono::TimeDelta::seconds(i as i64)),
+        })
+        .collect();
+
+        let transport = ReplayTransport::new(notifications, Some(100.0));
+        let mut device = WhoopDevice::with_transport(transport, db);
+        device.connect().await.expect("Unable to connect");
+
+        let started = std::time::Instant::now();
+        assert_eq!(device.replay_notifications().await.ok(), Some(2));
+        assert!(started.elapsed() >= Duration::from_millis(10));
+        assert!(!device.is_connected().await.expect("Transport failed"));
+
+        let writes = device.transport().writes();
+        assert_eq!(writes.len(), 1);
+        let history_end = WhoopPacket::from_data(&writes[0].1).expect("Invalid command");
+        assert_eq!(history_end.cmd, CommandNumber::HistoricalDataResult.as_u8());
+
+        let history = device
+            .database()
+            .search_history(SearchHistory::default())
+            .await
+            .expect("Unable to read history");
+        assert_eq!(history.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn registered_history_decoder() {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let known = hex::decode("aa5c00f02f0c053f940900da106966280080545401360195040000000000000000a34cff0050bf3b144efb3da4a4463f299c0dbf00004c42144efb3da4a4463f299c0dbff40155023b03530255016004010c020c2000000000000002e8c17c8d").expect("Invalid hex");
+        let known = WhoopPacket::from_data(&known).expect("Invalid packet");
+        let unknown = WhoopPacket::new(known.packet_type, known.seq, 42, known.data);
+
+        let mut whoop = OpenWhoop::new(db.clone()).with_history_decoder(42, |data| {
+            let decoder = whoop::HistoryDecoders::known()
+                .get(5)
+                .expect("Known version");
+            decoder(data)
+        });
+        let packet = db
+            .create_packet(DATA_FROM_STRAP, unknown.framed_packet())
+            .await
+            .expect("Unable to store packet");
+        whoop
+            .handle_packet(packet)
+            .await
+            .expect("Unable to handle packet");
+
+        assert_eq!(whoop.take_received_history().records, 1);
+    }
+
+    #[tokio::test]
+    async fn request_is_retried() {
+        let (device, mut strap) = device().await;
+        let mut device = device.with_request_policy(RequestPolicy {
+            timeout: Duration::from_millis(100),
+            retries: 1,
+        });
+        assert!(strap.is_subscribed(CMD_FROM_STRAP));
+
+        let strap = tokio::spawn(async move {
+            let enter_sync = next_command(&mut strap).await;
+            assert_eq!(enter_sync.cmd, CommandNumber::EnterHighFreqSync.as_u8());
+
+            // First attempt is left unanswered
+            let first = next_command(&mut strap).await;
+            let retry = next_command(&mut strap).await;
+            assert_eq!(first.cmd, retry.cmd);
+            assert_ne!(first.seq, retry.seq);
+
+            let mut data = vec![0x00, 0x00];
+            data.extend_from_slice(&1735831144u32.to_le_bytes());
+            let response =
+                WhoopPacket::new(PacketType::CommandResponse, retry.seq, retry.cmd, data);
+            strap.notify(CMD_FROM_STRAP, response.framed_packet());
+            strap
+        });
+
+        let response = device
+            .request(WhoopPacket::get_clock())
+            .await
+            .expect("No response");
+        assert_eq!(response, CommandResponse::Clock { unix: 1735831144 });
+
+        let strap = strap.await.expect("Strap failed");
+        strap.disconnect();
+        assert!(device.request(WhoopPacket::get_clock()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sync_history_from_memory_strap() {
+        let (mut device, mut strap) = device().await;
+
+        let strap = tokio::spawn(async move {
+            let _enter_sync = next_command(&mut strap).await;
+            let history_start = next_command(&mut strap).await;
+            assert_eq!(history_start.cmd, CommandNumber::SendHistoricalData.as_u8());
+
+            for packet in [
+                "aa5c00f02f0c053f940900da106966280080545401360195040000000000000000a34cff0050bf3b144efb3da4a4463f299c0dbf00004c42144efb3da4a4463f299c0dbff40155023b03530255016004010c020c2000000000000002e8c17c8d",
+                "aa1c00ab31370268ae7667702d32000000c7b6000010000000000000e01eba47",
+            ] {
+                strap.notify(DATA_FROM_STRAP, hex::decode(packet).expect("Invalid hex"));
+            }
+
+            let history_end = next_command(&mut strap).await;
+            assert_eq!(history_end.cmd, CommandNumber::HistoricalDataResult.as_u8());
+            assert_eq!(history_end.data[1..5], 46791u32.to_le_bytes());
+            strap.disconnect();
+        });
+
+        device.sync_history().await.expect("Sync failed");
+        strap.await.expect("Strap failed");
+
+        let history = device
+            .whoop
+            .database
+            .search_history(SearchHistory::default())
+            .await
+            .expect("Unable to read history");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].bpm, 54);
+    }
+
+    #[tokio::test]
+    async fn read_only_sync_aborts_without_read_pointer_ack() {
+        let (device, mut strap) = device().await;
+        let mut device = device.with_history_ack(HistoryAck::ReadOnly);
+
+        let strap = tokio::spawn(async move {
+            let _enter_sync = next_command(&mut strap).await;
+            let _history_start = next_command(&mut strap).await;
+            for packet in [
+                "aa5c00f02f0c053f940900da106966280080545401360195040000000000000000a34cff0050bf3b144efb3da4a4463f299c0dbf00004c42144efb3da4a4463f299c0dbff40155023b03530255016004010c020c2000000000000002e8c17c8d",
+                "aa1c00ab31370268ae7667702d32000000c7b6000010000000000000e01eba47",
+            ] {
+                strap.notify(DATA_FROM_STRAP, hex::decode(packet).expect("Invalid hex"));
+            }
+
+            // Response without header isn't an acknowledgement
+            let set_pointer = next_command(&mut strap).await;
+            assert_eq!(set_pointer.cmd, CommandNumber::SetReadPointer.as_u8());
+            let response = WhoopPacket::new(
+                PacketType::CommandResponse,
+                set_pointer.seq,
+                set_pointer.cmd,
+                vec![],
+            );
+            strap.notify(CMD_FROM_STRAP, response.framed_packet());
+
+            let abort = next_command(&mut strap).await;
+            assert_eq!(abort.cmd, CommandNumber::AbortHistoricalTransmits.as_u8());
+            strap
+        });
+
+        device.sync_history().await.expect("Sync failed");
+        strap.await.expect("Strap failed");
+    }
+}
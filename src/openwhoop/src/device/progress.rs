@@ -0,0 +1,181 @@
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+
+/// History readings handled since they were last taken from [`OpenWhoop`](crate::OpenWhoop)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReceivedHistory {
+    pub records: u64,
+    /// Unix time of oldest and newest reading
+    pub first: Option<u32>,
+    pub last: Option<u32>,
+}
+
+impl ReceivedHistory {
+    pub(crate) fn add(&mut self, unix: u32) {
+        self.records += 1;
+        self.first = Some(self.first.map_or(unix, |first| first.min(unix)));
+        self.last = Some(self.last.map_or(unix, |last| last.max(unix)));
+    }
+}
+
+/// Progress of history sync, reported when sync starts, after every batch and when it completes
+#[derive(Debug, Clone)]
+pub struct SyncProgress {
+    /// Batches strap ended with `HistoryEnd`
+    pub batches: u32,
+    pub received: ReceivedHistory,
+    /// History stored on strap when sync started, in units of `HistoryEnd` data
+    pub range: Option<Range<u32>>,
+    /// Position in range sync started from, after oldest record or last checkpoint
+    pub start: Option<u32>,
+    /// Data of last `HistoryEnd`
+    pub position: Option<u32>,
+    pub elapsed: Duration,
+    /// Strap reported that all history was sent
+    pub complete: bool,
+    started: Instant,
+}
+
+impl SyncProgress {
+    pub(crate) fn new() -> Self {
+        Self {
+            batches: 0,
+            received: ReceivedHistory::default(),
+            range: None,
+            start: None,
+            position: None,
+            elapsed: Duration::ZERO,
+            complete: false,
+            started: Instant::now(),
+        }
+    }
+
+    pub(crate) fn add_received(&mut self, received: ReceivedHistory) {
+        let ReceivedHistory {
+            records,
+            first,
+            last,
+        } = received;
+
+        self.received.records += records;
+        self.received.first = match (self.received.first, first) {
+            (Some(current), Some(first)) => Some(current.min(first)),
+            (current, first) => current.or(first),
+        };
+        self.received.last = self.received.last.max(last);
+    }
+
+    pub(crate) fn update_elapsed(&mut self) {
+        self.elapsed = self.started.elapsed();
+    }
+
+    fn range_fraction(&self, position: u32) -> Option<f64> {
+        let range = self.range.as_ref()?;
+        let total = range
+            .end
+            .checked_sub(range.start)
+            .filter(|total| *total > 0)?;
+        let done = position.saturating_sub(range.start).min(total);
+        Some(f64::from(done) / f64::from(total))
+    }
+
+    /// Time from oldest received reading to newest one, relative to time from oldest one to now
+    fn time_fraction(&self) -> Option<f64> {
+        let (first, last) = (self.received.first?, self.received.last?);
+        let total = Utc::now().timestamp() - i64::from(first);
+        if total <= 0 {
+            return None;
+        }
+
+        Some((f64::from(last - first) / total as f64).min(1.0))
+    }
+
+    /// Part of history downloaded, from position in range strap reported,
+    /// or from time covered by received readings if range isn't known
+    pub fn fraction(&self) -> Option<f64> {
+        if self.complete {
+            return Some(1.0);
+        }
+
+        self.position
+            .and_then(|position| self.range_fraction(position))
+            .or_else(|| self.time_fraction())
+    }
+
+    pub fn records_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+
+        self.received.records as f64 / seconds
+    }
+
+    /// Time until sync completes, estimated from part of history downloaded since sync started
+    pub fn eta(&self) -> Option<Duration> {
+        if self.complete {
+            return Some(Duration::ZERO);
+        }
+
+        let positions = self.start.zip(self.position);
+        let (initial, current) = match positions {
+            Some((start, position)) if self.range.is_some() => {
+                (self.range_fraction(start)?, self.range_fraction(position)?)
+            }
+            _ => (0.0, self.time_fraction()?),
+        };
+
+        let done = current - initial;
+        if done <= 0.0 {
+            return None;
+        }
+
+        Some(self.elapsed.mul_f64((1.0 - current) / done))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fraction_and_eta_from_range() {
+        let mut progress = SyncProgress::new();
+        progress.range = Some(100..356);
+        progress.start = Some(164);
+        progress.position = Some(228);
+        progress.elapsed = Duration::from_secs(30);
+
+        let mut received = ReceivedHistory::default();
+        for unix in [20, 10, 30] {
+            received.add(unix);
+        }
+        progress.add_received(received);
+        progress.add_received(ReceivedHistory {
+            records: 3,
+            first: Some(5),
+            last: Some(25),
+        });
+
+        assert_eq!(progress.fraction(), Some(0.5));
+        // Quarter of range took 30 seconds, half of it remains
+        assert_eq!(progress.eta(), Some(Duration::from_secs(60)));
+        assert_eq!(
+            progress.received,
+            ReceivedHistory {
+                records: 6,
+                first: Some(5),
+                last: Some(30),
+            }
+        );
+        assert_eq!(progress.records_per_second(), 0.2);
+
+        progress.complete = true;
+        assert_eq!(progress.fraction(), Some(1.0));
+        assert_eq!(progress.eta(), Some(Duration::ZERO));
+    }
+}
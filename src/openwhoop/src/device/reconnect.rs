@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+/// How strap is reconnected after connection drops, delay between attempts doubles every attempt
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+    /// Delay before first attempt
+    pub initial_delay: Duration,
+    /// Delay doesn't grow over this
+    pub max_delay: Duration,
+    /// Attempts after which reconnecting is given up
+    pub max_attempts: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: 10,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before `attempt`, counted from zero
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.checked_pow(attempt).unwrap_or(u32::MAX);
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            max_attempts: 10,
+        };
+
+        let delays = (0..7)
+            .map(|attempt| policy.delay(attempt))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [500, 1000, 2000, 4000, 8000, 10_000, 10_000].map(Duration::from_millis)
+        );
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(10));
+    }
+}
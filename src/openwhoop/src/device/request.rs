@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use whoop::{constants::PacketType, WhoopPacket};
+
+/// How long to wait for response to a command and how many times to resend it
+#[derive(Debug, Clone, Copy)]
+pub struct RequestPolicy {
+    /// Time to wait for response after every attempt
+    pub timeout: Duration,
+    /// Number of times command is resent after first attempt timed out
+    pub retries: u32,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            retries: 2,
+        }
+    }
+}
+
+/// Incrementing `seq` assigned to commands sent to strap.
+///
+/// `0` is skipped, packet builders use it so it doesn't identify any request
+#[derive(Debug, Default)]
+pub(crate) struct SequenceNumbers {
+    last: u8,
+}
+
+impl SequenceNumbers {
+    pub(crate) fn next(&mut self) -> u8 {
+        self.last = self.last.checked_add(1).unwrap_or(1);
+        self.last
+    }
+}
+
+/// Command that was sent and is waiting for its `COMMAND_RESPONSE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PendingRequest {
+    pub(crate) seq: u8,
+    pub(crate) cmd: u8,
+}
+
+impl PendingRequest {
+    pub(crate) fn new(packet: &WhoopPacket) -> Self {
+        Self {
+            seq: packet.seq,
+            cmd: packet.cmd,
+        }
+    }
+
+    /// Strap answers with same `seq` and `cmd` as the command it responds to
+    pub(crate) fn is_response(&self, packet: &WhoopPacket) -> bool {
+        packet.packet_type == PacketType::CommandResponse
+            && packet.seq == self.seq
+            && packet.cmd == self.cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_numbers_skip_zero() {
+        let mut seq = SequenceNumbers::default();
+        assert_eq!(seq.next(), 1);
+        assert_eq!(seq.next(), 2);
+
+        let mut seq = SequenceNumbers { last: u8::MAX };
+        assert_eq!(seq.next(), 1);
+    }
+
+    #[test]
+    fn match_response() {
+        let request = WhoopPacket::get_clock().with_seq(7);
+        let pending = PendingRequest::new(&request);
+
+        let response = |seq, cmd| WhoopPacket::new(PacketType::CommandResponse, seq, cmd, vec![]);
+        assert!(pending.is_response(&response(7, request.cmd)));
+        assert!(!pending.is_response(&response(8, request.cmd)));
+        assert!(!pending.is_response(&response(7, request.cmd.wrapping_add(1))));
+        assert!(!pending.is_response(&WhoopPacket::new(PacketType::Event, 7, request.cmd, vec![])));
+    }
+}
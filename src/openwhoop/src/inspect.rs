@@ -0,0 +1,354 @@
+//! Decoding of stored packets for auditing what a sync received
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    str::FromStr,
+};
+
+use chrono::NaiveDateTime;
+use db_entities::packets;
+use uuid::Uuid;
+use whoop::{
+    constants::{
+        CommandNumber, EventNumber, MetadataType, PacketType, CMD_FROM_STRAP, CMD_TO_STRAP,
+        DATA_FROM_STRAP, EVENTS_FROM_STRAP, MEMFAULT,
+    },
+    FrameDecoder, StrapEvent, WhoopData, WhoopPacket,
+};
+
+use crate::types::packets::PacketDirection;
+
+/// Summaries of decoded data are cut to this many characters
+const SUMMARY_LENGTH: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DecodeStatus {
+    /// Every frame that ended in packet was decoded
+    Decoded,
+    /// Frame is valid, but its data couldn't be decoded
+    Failed,
+    /// Packet doesn't end any frame, rest of frame is in following packets
+    Fragment,
+    /// Bytes that aren't part of any valid frame were dropped
+    Invalid,
+    /// Characteristic doesn't carry WHOOP frames
+    Raw,
+}
+
+impl Display for DecodeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            DecodeStatus::Decoded => "decoded",
+            DecodeStatus::Failed => "failed",
+            DecodeStatus::Fragment => "fragment",
+            DecodeStatus::Invalid => "invalid",
+            DecodeStatus::Raw => "raw",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for DecodeStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decoded" => Ok(DecodeStatus::Decoded),
+            "failed" => Ok(DecodeStatus::Failed),
+            "fragment" => Ok(DecodeStatus::Fragment),
+            "invalid" => Ok(DecodeStatus::Invalid),
+            "raw" => Ok(DecodeStatus::Raw),
+            _ => Err(format!("Unknown decode status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InspectedFrame {
+    pub packet_type: PacketType,
+    pub seq: u8,
+    pub cmd: u8,
+    /// Short description of decoded data, or reason it couldn't be decoded
+    pub summary: Result<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InspectedPacket {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub time: Option<NaiveDateTime>,
+    pub direction: PacketDirection,
+    pub length: usize,
+    /// Frames that were completed by this packet
+    pub frames: Vec<InspectedFrame>,
+    pub status: DecodeStatus,
+}
+
+/// Filters applied to decoded packets, packet matches if any of its frames matches
+#[derive(Debug, Default)]
+pub struct InspectFilter {
+    pub packet_type: Option<PacketType>,
+    pub cmd: Option<u8>,
+    pub status: Option<DecodeStatus>,
+}
+
+impl InspectFilter {
+    pub fn matches(&self, packet: &InspectedPacket) -> bool {
+        let frame_matches = |frame: &InspectedFrame| {
+            self.packet_type
+                .is_none_or(|packet_type| frame.packet_type == packet_type)
+                && self.cmd.is_none_or(|cmd| frame.cmd == cmd)
+        };
+
+        let frames_match = (self.packet_type.is_none() && self.cmd.is_none())
+            || packet.frames.iter().any(frame_matches);
+
+        frames_match && self.status.is_none_or(|status| packet.status == status)
+    }
+}
+
+/// Frame counts of one packet type and command
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCounts {
+    pub total: usize,
+    pub failed: usize,
+}
+
+impl FrameCounts {
+    pub fn failure_rate(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        self.failed as f64 / self.total as f64
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InspectStats {
+    pub packets: usize,
+    pub bytes: usize,
+    pub per_status: BTreeMap<DecodeStatus, usize>,
+    /// Keyed by packet type and command, type is kept as number so map is ordered
+    pub per_command: BTreeMap<(u8, u8), FrameCounts>,
+}
+
+impl InspectStats {
+    pub fn new<'a>(packets: impl IntoIterator<Item = &'a InspectedPacket>) -> Self {
+        let mut stats = Self::default();
+        for packet in packets {
+            stats.packets += 1;
+            stats.bytes += packet.length;
+            *stats.per_status.entry(packet.status).or_default() += 1;
+
+            for frame in &packet.frames {
+                let counts = stats
+                    .per_command
+                    .entry((frame.packet_type.as_u8(), frame.cmd))
+                    .or_default();
+                counts.total += 1;
+                counts.failed += usize::from(frame.summary.is_err());
+            }
+        }
+
+        stats
+    }
+}
+
+/// Short name of WHOOP characteristic
+pub fn characteristic_name(uuid: Uuid) -> &'static str {
+    match uuid {
+        CMD_TO_STRAP => "cmd_to_strap",
+        CMD_FROM_STRAP => "cmd_from_strap",
+        EVENTS_FROM_STRAP => "events_from_strap",
+        DATA_FROM_STRAP => "data_from_strap",
+        MEMFAULT => "memfault",
+        _ => "unknown",
+    }
+}
+
+/// Name of command or event number, number itself if it isn't known
+pub fn command_name(packet_type: PacketType, cmd: u8) -> String {
+    let name = match packet_type {
+        PacketType::Command | PacketType::CommandResponse => {
+            CommandNumber::from_u8(cmd).map(|command| format!("{:?}", command))
+        }
+        PacketType::Event => EventNumber::from_u8(cmd).map(|event| format!("{:?}", event)),
+        PacketType::Metadata => {
+            MetadataType::from_u8(cmd).map(|metadata| format!("{:?}", metadata))
+        }
+        _ => None,
+    };
+
+    name.unwrap_or_else(|| cmd.to_string())
+}
+
+fn summary(uuid: Uuid, packet: WhoopPacket) -> Result<String, String> {
+    if packet.packet_type == PacketType::Command {
+        return Ok(command_name(packet.packet_type, packet.cmd));
+    }
+
+    if uuid == EVENTS_FROM_STRAP {
+        return StrapEvent::from_packet(&packet)
+            .map(|event| format!("{:?}", event))
+            .map_err(|error| error.to_string());
+    }
+
+    let data = WhoopData::from_packet(packet).map_err(|error| error.to_string())?;
+    let summary = match data {
+        WhoopData::RealtimeImu(imu) | WhoopData::HistoricalImu(imu) => {
+            format!(
+                "Imu {{ unix: {}, samples: {} }}",
+                imu.unix,
+                imu.samples.len()
+            )
+        }
+        WhoopData::RawOptical(optical) => format!(
+            "RawOptical {{ unix: {}, sample_rate: {}, samples: {} }}",
+            optical.unix,
+            optical.sample_rate,
+            optical.samples.len()
+        ),
+        data => format!("{:?}", data),
+    };
+
+    Ok(summary.chars().take(SUMMARY_LENGTH).collect())
+}
+
+/// Decodes stored packets, frames are reassembled per characteristic so packets have to be
+/// passed in order they were received
+pub fn inspect_packets(models: Vec<packets::Model>) -> Vec<InspectedPacket> {
+    let mut decoders: HashMap<Uuid, FrameDecoder> = HashMap::new();
+
+    models
+        .into_iter()
+        .map(|model| {
+            let direction = model
+                .direction
+                .as_deref()
+                .and_then(|direction| direction.parse().ok())
+                .unwrap_or(PacketDirection::of(model.uuid));
+
+            let mut packet = InspectedPacket {
+                id: model.id,
+                uuid: model.uuid,
+                time: model.time,
+                direction,
+                length: model.bytes.len(),
+                frames: Vec::new(),
+                status: DecodeStatus::Raw,
+            };
+
+            if model.uuid == MEMFAULT {
+                return packet;
+            }
+
+            let decoder = decoders.entry(model.uuid).or_default();
+            let skipped = decoder.skipped();
+            packet.frames = decoder
+                .decode(&model.bytes)
+                .into_iter()
+                .map(|frame| InspectedFrame {
+                    packet_type: frame.packet_type,
+                    seq: frame.seq,
+                    cmd: frame.cmd,
+                    summary: summary(model.uuid, frame),
+                })
+                .collect();
+
+            packet.status = if decoder.skipped() > skipped {
+                DecodeStatus::Invalid
+            } else if packet.frames.is_empty() {
+                DecodeStatus::Fragment
+            } else if packet.frames.iter().any(|frame| frame.summary.is_err()) {
+                DecodeStatus::Failed
+            } else {
+                DecodeStatus::Decoded
+            };
+
+            packet
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = "aa1c00ab31370268ae7667702d32000000c7b6000010000000000000e01eba47";
+
+    fn model(id: i32, uuid: Uuid, bytes: Vec<u8>) -> packets::Model {
+        packets::Model {
+            id,
+            uuid,
+            bytes,
+            time: None,
+            direction: None,
+        }
+    }
+
+    #[test]
+    fn inspect_fragments_and_failures() {
+        let metadata = hex::decode(METADATA).expect("Invalid hex");
+        let (first, second) = metadata.split_at(10);
+        let unknown = WhoopPacket::new(PacketType::ConsoleLogs, 0, 0, vec![]).framed_packet();
+        let wrist_on = WhoopPacket::new(
+            PacketType::Event,
+            0,
+            EventNumber::WristOn.as_u8(),
+            vec![0x00, 0x00, 0x2d, 0x32, 0x00, 0x00, 0x00],
+        )
+        .framed_packet();
+
+        let packets = inspect_packets(vec![
+            model(1, CMD_TO_STRAP, WhoopPacket::get_clock().framed_packet()),
+            model(2, DATA_FROM_STRAP, first.to_vec()),
+            model(3, DATA_FROM_STRAP, second.to_vec()),
+            model(4, DATA_FROM_STRAP, unknown),
+            model(5, DATA_FROM_STRAP, vec![0x00, 0x01]),
+            model(6, MEMFAULT, vec![0x01]),
+            model(7, EVENTS_FROM_STRAP, wrist_on),
+        ]);
+
+        let statuses = packets
+            .iter()
+            .map(|packet| packet.status)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                DecodeStatus::Decoded,
+                DecodeStatus::Fragment,
+                DecodeStatus::Decoded,
+                DecodeStatus::Failed,
+                DecodeStatus::Invalid,
+                DecodeStatus::Raw,
+                DecodeStatus::Decoded,
+            ]
+        );
+        assert_eq!(packets[0].direction, PacketDirection::ToStrap);
+        assert_eq!(packets[0].frames[0].summary, Ok("GetClock".to_owned()));
+
+        let filter = InspectFilter {
+            packet_type: Some(PacketType::Metadata),
+            ..Default::default()
+        };
+        let matching = packets
+            .iter()
+            .filter(|packet| filter.matches(packet))
+            .map(|packet| packet.id)
+            .collect::<Vec<_>>();
+        assert_eq!(matching, vec![3]);
+
+        let stats = InspectStats::new(&packets);
+        let event = &packets[6].frames[0];
+        assert_eq!(command_name(event.packet_type, event.cmd), "WristOn");
+
+        assert_eq!(stats.packets, 7);
+        assert_eq!(stats.per_status.get(&DecodeStatus::Decoded), Some(&3));
+        let console = stats.per_command[&(PacketType::ConsoleLogs.as_u8(), 0)];
+        assert_eq!(console.failure_rate(), 1.0);
+    }
+}
@@ -2,10 +2,12 @@
 extern crate log;
 
 mod db;
-pub use db::{DatabaseHandler, SearchHistory};
+pub use db::{DatabaseHandler, SearchHistory, SearchPackets};
 
 mod device;
-pub use device::WhoopDevice;
+pub use device::{
+    HistoryAck, ReceivedHistory, ReconnectPolicy, RequestPolicy, SyncProgress, WhoopDevice,
+};
 
 mod openwhoop;
 pub use openwhoop::OpenWhoop;
@@ -14,4 +16,14 @@ pub mod algo;
 
 pub mod types;
 
+pub mod transport;
+
+pub mod btsnoop;
+
+pub mod capture;
+
+pub mod inspect;
+
+pub mod daemon;
+
 pub(crate) mod helpers;
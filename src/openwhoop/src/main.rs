@@ -1,18 +1,39 @@
 #[macro_use]
 extern crate log;
 
-use std::time::Duration;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use anyhow::anyhow;
 use btleplug::{
     api::{BDAddr, Central, Manager as _, Peripheral as _, ScanFilter},
     platform::{Adapter, Manager, Peripheral},
 };
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Local, NaiveDateTime};
+use clap::{Args, Parser, Subcommand};
 use dotenv::dotenv;
-use openwhoop::{algo::SleepConsistencyAnalyzer, DatabaseHandler, OpenWhoop, WhoopDevice};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar, ProgressStyle};
+use indicatif_log_bridge::LogWrapper;
+use openwhoop::{
+    algo::{SleepConsistencyAnalyzer, SpO2Calibration, SpO2Config},
+    btsnoop,
+    capture::{self, CaptureFormat},
+    daemon::{Daemon, DaemonConfig},
+    inspect::{self, DecodeStatus, InspectFilter, InspectStats},
+    transport::{BleTransport, RecordedNotification, ReplayTransport},
+    types::packets::PacketDirection,
+    DatabaseHandler, HistoryAck, OpenWhoop, ReconnectPolicy, RequestPolicy, SearchPackets,
+    SyncProgress, WhoopDevice,
+};
 use tokio::time::sleep;
-use whoop::{constants::WHOOP_SERVICE, WhoopPacket};
+use uuid::Uuid;
+use whoop::{
+    constants::{PacketType, WHOOP_SERVICE},
+    CommandResponse, WhoopPacket,
+};
 
 #[derive(Parser)]
 pub struct OpenWhoopCli {
@@ -24,16 +45,199 @@ pub struct OpenWhoopCli {
     pub subcommand: OpenWhoopCommand,
 }
 
+#[derive(Args)]
+pub struct ReconnectArgs {
+    /// Times strap is reconnected after connection drops, before sync is given up
+    #[arg(long, default_value_t = 10)]
+    pub max_reconnects: u32,
+    /// Seconds before first reconnect attempt, doubles with every attempt
+    #[arg(long, default_value_t = 1)]
+    pub reconnect_delay: u64,
+    /// Reconnect delay doesn't grow over this many seconds
+    #[arg(long, default_value_t = 60)]
+    pub max_reconnect_delay: u64,
+}
+
+impl From<ReconnectArgs> for ReconnectPolicy {
+    fn from(args: ReconnectArgs) -> Self {
+        Self {
+            initial_delay: Duration::from_secs(args.reconnect_delay),
+            max_delay: Duration::from_secs(args.max_reconnect_delay),
+            max_attempts: args.max_reconnects,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct HistoryAckArgs {
+    /// Don't acknowledge history batches so strap doesn't trim them, e.g. when official app
+    /// syncs same strap
+    #[arg(long)]
+    pub no_ack: bool,
+    /// Abort transmission after first batch, instead of moving read pointer past every batch
+    #[arg(long, requires = "no_ack")]
+    pub abort: bool,
+}
+
+impl From<HistoryAckArgs> for HistoryAck {
+    fn from(args: HistoryAckArgs) -> Self {
+        match (args.no_ack, args.abort) {
+            (false, _) => HistoryAck::Acknowledge,
+            (true, false) => HistoryAck::ReadOnly,
+            (true, true) => HistoryAck::Abort,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum OpenWhoopCommand {
     Scan,
     DownloadHistory {
         #[arg(long, env)]
         whoop_addr: BDAddr,
+        #[command(flatten)]
+        reconnect: ReconnectArgs,
+        #[command(flatten)]
+        history_ack: HistoryAckArgs,
+        /// Sync from oldest stored record instead of continuing from last checkpoint
+        #[arg(long)]
+        no_resume: bool,
+    },
+    /// Sync history on schedule whenever strap is in range, stops on SIGTERM or Ctrl-C
+    Daemon {
+        #[arg(long, env)]
+        whoop_addr: BDAddr,
+        /// Seconds from end of one scan or sync to start of next scan
+        #[arg(long, default_value_t = 900)]
+        interval: u64,
+        /// Seconds strap is scanned for before it is considered out of range
+        #[arg(long, default_value_t = 30)]
+        scan_timeout: u64,
+        /// JSON file with state of daemon, rewritten on every state change
+        #[arg(long, env = "OPENWHOOP_STATUS_FILE")]
+        status_file: Option<PathBuf>,
+        #[command(flatten)]
+        reconnect: ReconnectArgs,
+        #[command(flatten)]
+        history_ack: HistoryAckArgs,
+        /// Sync from oldest stored record instead of continuing from last checkpoint
+        #[arg(long)]
+        no_resume: bool,
+    },
+    Live {
+        #[arg(long, env)]
+        whoop_addr: BDAddr,
+        /// Store readings and raw packets in database
+        #[arg(long)]
+        store: bool,
+    },
+    /// Capture raw optical (PPG) samples
+    CaptureRaw {
+        #[arg(long, env)]
+        whoop_addr: BDAddr,
+        /// Capture duration in seconds
+        #[arg(long, default_value_t = 60)]
+        duration: u64,
+    },
+    /// Print clock, battery level and firmware versions of strap
+    Info {
+        #[arg(long, env)]
+        whoop_addr: BDAddr,
+        /// Seconds to wait for every response
+        #[arg(long, default_value_t = 5)]
+        timeout: u64,
+        /// Times command is resent if strap doesn't respond
+        #[arg(long, default_value_t = 2)]
+        retries: u32,
+    },
+    /// Replay stored packets through sync flow, decoded data is stored to target database
+    Replay {
+        #[arg(long)]
+        target_database_url: String,
+        /// `1` keeps original timing, `10` is ten times faster, without it packets are replayed
+        /// as fast as they are processed
+        #[arg(long)]
+        speed: Option<f64>,
+        /// Replay packets with id greater than this
+        #[arg(long, default_value_t = 0)]
+        after_id: i32,
+        /// Maximal number of replayed packets
+        #[arg(long)]
+        limit: Option<u64>,
+    },
+    /// Import writes and notifications of WHOOP characteristics from btsnoop HCI log
+    ImportBtsnoop {
+        /// Log captured by Android, usually `btsnoop_hci.log` from bug report
+        file: PathBuf,
+    },
+    /// Export stored packets as capture with synthetic ATT framing, for Wireshark
+    ExportCapture {
+        output: PathBuf,
+        /// `btsnoop` or `pcapng`, by default implied from extension of output
+        #[arg(long)]
+        format: Option<CaptureFormat>,
+        /// Export packets with id greater than this
+        #[arg(long)]
+        after_id: Option<i32>,
+        /// Export packets with id less than this
+        #[arg(long)]
+        before_id: Option<i32>,
+        /// Export packets received after this time, e.g. `2025-01-02T15:19:04`
+        #[arg(long)]
+        from: Option<NaiveDateTime>,
+        /// Export packets received before this time
+        #[arg(long)]
+        to: Option<NaiveDateTime>,
+        /// Export only packets of this characteristic
+        #[arg(long)]
+        uuid: Option<Uuid>,
+    },
+    /// List stored packets with decoded summaries and statistics
+    Packets {
+        /// Only packets of this characteristic
+        #[arg(long)]
+        uuid: Option<Uuid>,
+        /// Only packets with frames of this type, name (e.g. `Metadata`) or number
+        #[arg(long, value_parser = parse_packet_type)]
+        packet_type: Option<PacketType>,
+        /// Only packets with frames of this command, event or metadata number
+        #[arg(long)]
+        cmd: Option<u8>,
+        /// Only packets with status: `decoded`, `failed`, `fragment`, `invalid` or `raw`
+        #[arg(long)]
+        status: Option<DecodeStatus>,
+        #[arg(long)]
+        after_id: Option<i32>,
+        #[arg(long)]
+        before_id: Option<i32>,
+        /// Maximal number of inspected packets, before filtering by type, command and status
+        #[arg(long)]
+        limit: Option<u64>,
+        /// Print only statistics
+        #[arg(long)]
+        stats: bool,
     },
     ReRun,
     DetectEvents,
     SleepStats,
+    /// Calculate SpO2 for stored sleeps from raw optical samples
+    Spo2 {
+        /// Calibration curve `SpO2 = a + b * R + c * R^2`
+        #[arg(long, default_value_t = 110.0, allow_hyphen_values = true)]
+        calibration_a: f64,
+        #[arg(long, default_value_t = -25.0, allow_hyphen_values = true)]
+        calibration_b: f64,
+        #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+        calibration_c: f64,
+    },
+    /// Calculate nightly skin temperature and deviation from personal baseline
+    SkinTemp,
+    /// Compare heart rate estimated from raw PPG with heart rate from strap
+    PpgStats {
+        /// Fill missing RR intervals from PPG estimate
+        #[arg(long)]
+        fill_rr: bool,
+    },
 }
 
 #[tokio::main]
@@ -42,71 +246,367 @@ async fn main() -> anyhow::Result<()> {
         println!("{}", error);
     }
 
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .filter_module("sqlx::query", log::LevelFilter::Off)
-        .filter_module("sea_orm_migration::migrator", log::LevelFilter::Off)
-        .init();
+    let logger =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+            .filter_module("sqlx::query", log::LevelFilter::Off)
+            .filter_module("sea_orm_migration::migrator", log::LevelFilter::Off)
+            .build();
+    let level = logger.filter();
+    // Logs are printed above progress bars instead of breaking them
+    let progress_bars = MultiProgress::new();
+    LogWrapper::new(progress_bars.clone(), logger).try_init()?;
+    log::set_max_level(level);
 
     let cli = OpenWhoopCli::parse();
     let db_handler = DatabaseHandler::new(cli.database_url).await;
 
-    let manager = Manager::new().await?;
-    let adapter = match cli.ble_interface {
-        Some(interface) => {
-            let adapters = manager.adapters().await?;
-            let mut c_adapter = Err(anyhow!("Adapter: `{}` not found", interface));
-            for adapter in adapters {
-                let name = adapter.adapter_info().await?;
-                if name.starts_with(&interface) {
-                    c_adapter = Ok(adapter);
-                    break;
-                }
-            }
-
-            c_adapter?
-        }
-        None => {
-            let adapters = manager.adapters().await?;
-            adapters
-                .into_iter()
-                .next()
-                .ok_or(anyhow!("No BLE adapters found"))?
-        }
-    };
+    let ble_interface = cli.ble_interface;
 
     match cli.subcommand {
         OpenWhoopCommand::Scan => {
-            scan_command(adapter, None).await?;
+            scan_command(ble_adapter(ble_interface).await?, None).await?;
             Ok(())
         }
-        OpenWhoopCommand::DownloadHistory { whoop_addr } => {
-            let peripheral = scan_command(adapter, Some(whoop_addr)).await?;
-            let mut whoop = WhoopDevice::new(peripheral, db_handler);
+        OpenWhoopCommand::DownloadHistory {
+            whoop_addr,
+            reconnect,
+            history_ack,
+            no_resume,
+        } => {
+            let peripheral =
+                scan_command(ble_adapter(ble_interface).await?, Some(whoop_addr)).await?;
+            let bar = progress_bars.add(sync_progress_bar()?);
+            let on_progress = {
+                let bar = bar.clone();
+                move |progress: &SyncProgress| update_sync_progress_bar(&bar, progress)
+            };
+            let mut whoop = WhoopDevice::new(peripheral, db_handler)
+                .with_reconnect_policy(reconnect.into())
+                .with_history_ack(history_ack.into())
+                .with_resume(!no_resume)
+                .with_progress(on_progress);
 
             whoop.connect().await?;
             whoop.initialize().await?;
 
             let result = whoop.sync_history().await;
+            bar.abandon();
             if let Err(e) = result {
                 error!("{}", e);
             }
 
-            loop {
-                if let Ok(true) = whoop.is_connected().await {
-                    whoop
-                        .send_command(WhoopPacket::exit_high_freq_sync())
-                        .await?;
-                    break;
-                } else {
-                    whoop.connect().await?;
-                    sleep(Duration::from_secs(1)).await;
+            if !whoop.is_connected().await.unwrap_or_default() {
+                whoop.reconnect().await?;
+            }
+
+            whoop
+                .send_command(WhoopPacket::exit_high_freq_sync())
+                .await?;
+
+            Ok(())
+        }
+        OpenWhoopCommand::Daemon {
+            whoop_addr,
+            interval,
+            scan_timeout,
+            status_file,
+            reconnect,
+            history_ack,
+            no_resume,
+        } => {
+            let adapter = ble_adapter(ble_interface).await?;
+            let find_strap = || {
+                let adapter = adapter.clone();
+                async move {
+                    let peripheral = scan_command(adapter, Some(whoop_addr)).await?;
+                    Ok(BleTransport::new(peripheral))
+                }
+            };
+
+            let mut daemon = Daemon::new(
+                db_handler,
+                DaemonConfig {
+                    interval: Duration::from_secs(interval),
+                    scan_timeout: Duration::from_secs(scan_timeout),
+                    reconnect_policy: reconnect.into(),
+                    history_ack: history_ack.into(),
+                    resume: !no_resume,
+                    status_file,
+                },
+            );
+
+            daemon.run(find_strap, shutdown_signal()).await
+        }
+        OpenWhoopCommand::Live { whoop_addr, store } => {
+            let peripheral =
+                scan_command(ble_adapter(ble_interface).await?, Some(whoop_addr)).await?;
+            let mut whoop = WhoopDevice::new(peripheral, db_handler);
+
+            whoop.connect().await?;
+            whoop.initialize().await?;
+
+            whoop
+                .live_heart_rate(store, |reading| {
+                    let time = DateTime::from_timestamp(reading.unix.into(), 0)
+                        .map(|time| time.with_timezone(&Local).naive_local());
+                    match time {
+                        Some(time) => {
+                            println!("{} bpm: {}, rr: {:?}", time, reading.bpm, reading.rr)
+                        }
+                        None => println!("bpm: {}, rr: {:?}", reading.bpm, reading.rr),
+                    }
+                })
+                .await?;
+
+            whoop
+                .send_command(WhoopPacket::exit_high_freq_sync())
+                .await?;
+
+            Ok(())
+        }
+        OpenWhoopCommand::CaptureRaw {
+            whoop_addr,
+            duration,
+        } => {
+            let peripheral =
+                scan_command(ble_adapter(ble_interface).await?, Some(whoop_addr)).await?;
+            let mut whoop = WhoopDevice::new(peripheral, db_handler);
+
+            whoop.connect().await?;
+            whoop.initialize().await?;
+            whoop
+                .capture_raw_data(Duration::from_secs(duration))
+                .await?;
+
+            whoop
+                .send_command(WhoopPacket::exit_high_freq_sync())
+                .await?;
+
+            Ok(())
+        }
+        OpenWhoopCommand::Info {
+            whoop_addr,
+            timeout,
+            retries,
+        } => {
+            let peripheral =
+                scan_command(ble_adapter(ble_interface).await?, Some(whoop_addr)).await?;
+            let mut whoop =
+                WhoopDevice::new(peripheral, db_handler).with_request_policy(RequestPolicy {
+                    timeout: Duration::from_secs(timeout),
+                    retries,
+                });
+
+            whoop.connect().await?;
+            whoop.initialize().await?;
+
+            for packet in [
+                WhoopPacket::get_clock(),
+                WhoopPacket::get_battery_level(),
+                WhoopPacket::version_info(),
+            ] {
+                match whoop.request(packet).await? {
+                    CommandResponse::Clock { unix } => {
+                        let time = DateTime::from_timestamp(unix.into(), 0)
+                            .map(|time| time.with_timezone(&Local));
+                        println!("Clock: {:?}", time);
+                    }
+                    CommandResponse::BatteryLevel { level } => {
+                        println!("Battery: {:.1}%", f64::from(level) / 10.0)
+                    }
+                    CommandResponse::VersionInfo { harvard, boylston } => {
+                        println!("Harvard: {}, Boylston: {}", harvard, boylston)
+                    }
+                    response => println!("{:?}", response),
                 }
             }
 
+            whoop
+                .send_command(WhoopPacket::exit_high_freq_sync())
+                .await?;
+
+            Ok(())
+        }
+        OpenWhoopCommand::Replay {
+            target_database_url,
+            speed,
+            after_id,
+            limit,
+        } => {
+            let notifications = db_handler
+                .get_session_packets(after_id, limit)
+                .await?
+                .into_iter()
+                .filter(|packet| PacketDirection::of(packet.uuid) == PacketDirection::FromStrap)
+                .map(RecordedNotification::from)
+                .collect::<Vec<_>>();
+            info!("Replaying {} packets", notifications.len());
+
+            let target = DatabaseHandler::new(target_database_url).await;
+            let transport = ReplayTransport::new(notifications, speed);
+            let mut whoop = WhoopDevice::with_transport(transport, target);
+
+            let start = Instant::now();
+            whoop.connect().await?;
+            let count = whoop.replay_notifications().await?;
+            println!(
+                "Replayed {} packets in {:.2?}, {} commands were sent in response",
+                count,
+                start.elapsed(),
+                whoop.transport().writes().len()
+            );
+
+            Ok(())
+        }
+        OpenWhoopCommand::ImportBtsnoop { file } => {
+            let data = std::fs::read(&file)?;
+            let records = btsnoop::parse_records(&data)?;
+            let extracted = btsnoop::extract_whoop_packets(&records);
+
+            for (handle, uuid) in &extracted.discovered {
+                info!("Handle {:#06x} discovered as {}", handle, uuid);
+            }
+            for (handle, uuid) in &extracted.inferred {
+                info!("Handle {:#06x} inferred as {}", handle, uuid);
+            }
+            if extracted.skipped > 0 {
+                warn!("Skipped {} values on unknown handles", extracted.skipped);
+            }
+
+            let mut counts = BTreeMap::new();
+            for packet in &extracted.packets {
+                *counts
+                    .entry((packet.uuid, packet.direction.to_string()))
+                    .or_insert(0) += 1;
+            }
+
+            let stored = db_handler
+                .create_captured_packets(&extracted.packets)
+                .await?;
+            println!("Imported {} packets from {} records", stored, records.len());
+            for ((uuid, direction), count) in counts {
+                println!("{} ({}): {}", uuid, direction, count);
+            }
+
+            Ok(())
+        }
+        OpenWhoopCommand::ExportCapture {
+            output,
+            format,
+            after_id,
+            before_id,
+            from,
+            to,
+            uuid,
+        } => {
+            let models = db_handler
+                .search_packets(SearchPackets {
+                    after_id,
+                    before_id,
+                    from,
+                    to,
+                    uuid,
+                    limit: None,
+                })
+                .await?;
+
+            let packets = capture::stored_packets(models);
+            let format = format.unwrap_or(CaptureFormat::of(&output));
+            std::fs::write(&output, capture::export(&packets, format))?;
+            println!(
+                "Exported {} packets to {} ({})",
+                packets.len(),
+                output.display(),
+                format
+            );
+
+            Ok(())
+        }
+        OpenWhoopCommand::Packets {
+            uuid,
+            packet_type,
+            cmd,
+            status,
+            after_id,
+            before_id,
+            limit,
+            stats,
+        } => {
+            let models = db_handler
+                .search_packets(SearchPackets {
+                    after_id,
+                    before_id,
+                    uuid,
+                    limit,
+                    ..Default::default()
+                })
+                .await?;
+
+            let filter = InspectFilter {
+                packet_type,
+                cmd,
+                status,
+            };
+            let packets = inspect::inspect_packets(models)
+                .into_iter()
+                .filter(|packet| filter.matches(packet))
+                .collect::<Vec<_>>();
+
+            if !stats {
+                for packet in &packets {
+                    let time = packet
+                        .time
+                        .map(|time| time.to_string())
+                        .unwrap_or_else(|| "-".to_owned());
+                    println!(
+                        "#{} {} {} ({}) {} bytes, {}",
+                        packet.id,
+                        time,
+                        inspect::characteristic_name(packet.uuid),
+                        packet.direction,
+                        packet.length,
+                        packet.status
+                    );
+
+                    for frame in &packet.frames {
+                        let summary = match &frame.summary {
+                            Ok(summary) => summary.clone(),
+                            Err(error) => format!("failed: {}", error),
+                        };
+                        println!(
+                            "    {:?} seq: {} cmd: {} {}",
+                            frame.packet_type,
+                            frame.seq,
+                            inspect::command_name(frame.packet_type, frame.cmd),
+                            summary
+                        );
+                    }
+                }
+                println!();
+            }
+
+            let stats = InspectStats::new(&packets);
+            println!("Packets: {}, bytes: {}", stats.packets, stats.bytes);
+            for (status, count) in &stats.per_status {
+                println!("  {}: {}", status, count);
+            }
+            println!("Frames per type and command:");
+            for ((packet_type, cmd), counts) in &stats.per_command {
+                let packet_type = PacketType::from_u8(*packet_type).expect("Type of decoded frame");
+                println!(
+                    "  {:?} {}: {}, failed: {} ({:.1}%)",
+                    packet_type,
+                    inspect::command_name(packet_type, *cmd),
+                    counts.total,
+                    counts.failed,
+                    counts.failure_rate() * 100.0
+                );
+            }
+
             Ok(())
         }
         OpenWhoopCommand::ReRun => {
-            let whoop = OpenWhoop::new(db_handler.clone());
+            let mut whoop = OpenWhoop::new(db_handler.clone());
             let mut id = 0;
             loop {
                 let packets = db_handler.get_packets(id).await?;
@@ -138,9 +638,141 @@ async fn main() -> anyhow::Result<()> {
             println!("{:#?}", metrics);
             Ok(())
         }
+        OpenWhoopCommand::Spo2 {
+            calibration_a,
+            calibration_b,
+            calibration_c,
+        } => {
+            let config = SpO2Config {
+                calibration: SpO2Calibration {
+                    a: calibration_a,
+                    b: calibration_b,
+                    c: calibration_c,
+                },
+                ..Default::default()
+            };
+
+            let whoop = OpenWhoop::new(db_handler).with_spo2_config(config);
+            whoop.calculate_spo2().await?;
+            Ok(())
+        }
+        OpenWhoopCommand::SkinTemp => {
+            let whoop = OpenWhoop::new(db_handler);
+            whoop.calculate_skin_temp().await?;
+            Ok(())
+        }
+        OpenWhoopCommand::PpgStats { fill_rr } => {
+            let whoop = OpenWhoop::new(db_handler);
+            let agreement = whoop.ppg_heart_rate(fill_rr).await?;
+            println!("{:#?}", agreement);
+            Ok(())
+        }
     }
 }
 
+/// Packet type by name, case insensitive, or by number
+fn parse_packet_type(value: &str) -> Result<PacketType, String> {
+    let packet_type = match value.parse::<u8>() {
+        Ok(number) => PacketType::from_u8(number),
+        Err(_) => (0..=u8::MAX)
+            .filter_map(PacketType::from_u8)
+            .find(|packet_type| format!("{:?}", packet_type).eq_ignore_ascii_case(value)),
+    };
+
+    packet_type.ok_or(format!("Unknown packet type: {}", value))
+}
+
+/// Position of bar is in tenths of percent
+fn sync_progress_bar() -> anyhow::Result<ProgressBar> {
+    let style = ProgressStyle::with_template("{elapsed_precise} [{bar:40}] {percent:>3}% {msg}")?
+        .progress_chars("=> ");
+    Ok(ProgressBar::new(1000).with_style(style))
+}
+
+fn update_sync_progress_bar(bar: &ProgressBar, progress: &SyncProgress) {
+    if let Some(fraction) = progress.fraction() {
+        bar.set_position((fraction * 1000.0).round() as u64);
+    }
+
+    let until = progress
+        .received
+        .last
+        .and_then(|unix| DateTime::from_timestamp(unix.into(), 0))
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_owned());
+    let eta = progress
+        .eta()
+        .map(|eta| HumanDuration(eta).to_string())
+        .unwrap_or_else(|| "-".to_owned());
+
+    bar.set_message(format!(
+        "batches: {}, records: {}, until: {}, {:.0} records/s, ETA: {}",
+        progress.batches,
+        progress.received.records,
+        until,
+        progress.records_per_second(),
+        eta
+    ));
+}
+
+/// Completes on Ctrl-C or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(error) => {
+                error!("Unable to listen for SIGTERM: {}", error);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutting down");
+}
+
+/// Adapter is created only by commands that talk to strap, others work without Bluetooth
+async fn ble_adapter(ble_interface: Option<String>) -> anyhow::Result<Adapter> {
+    let manager = Manager::new().await?;
+    let adapter = match ble_interface {
+        Some(interface) => {
+            let adapters = manager.adapters().await?;
+            let mut c_adapter = Err(anyhow!("Adapter: `{}` not found", interface));
+            for adapter in adapters {
+                let name = adapter.adapter_info().await?;
+                if name.starts_with(&interface) {
+                    c_adapter = Ok(adapter);
+                    break;
+                }
+            }
+
+            c_adapter?
+        }
+        None => {
+            let adapters = manager.adapters().await?;
+            adapters
+                .into_iter()
+                .next()
+                .ok_or(anyhow!("No BLE adapters found"))?
+        }
+    };
+
+    Ok(adapter)
+}
+
 async fn scan_command(
     adapter: Adapter,
     peripheral_addr: Option<BDAddr>,
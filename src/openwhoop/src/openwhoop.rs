@@ -1,12 +1,21 @@
+use std::collections::HashMap;
+
 use btleplug::api::ValueNotification;
+use chrono::{NaiveDateTime, TimeDelta};
 use db_entities::packets;
+use uuid::Uuid;
 use whoop::{
-    constants::{MetadataType, DATA_FROM_STRAP},
-    Activity, HistoryReading, WhoopData, WhoopPacket,
+    constants::{MetadataType, CMD_FROM_STRAP, DATA_FROM_STRAP, EVENTS_FROM_STRAP},
+    Activity, FrameDecoder, HistoryDecoder, HistoryDecoders, HistoryReading, StrapEvent, WhoopData,
+    WhoopError, WhoopPacket,
 };
 
 use crate::{
-    algo::{activity::MAX_SLEEP_PAUSE, ActivityPeriod, SleepCycle},
+    algo::{
+        activity::MAX_SLEEP_PAUSE, ActivityPeriod, PpgAgreement, PpgHeartRate, SkinTemperature,
+        SleepCycle, SpO2Config, SpO2Reading,
+    },
+    device::ReceivedHistory,
     helpers::format_hm::FormatHM,
     types::activities,
     DatabaseHandler, SearchHistory,
@@ -14,11 +23,49 @@ use crate::{
 
 pub struct OpenWhoop {
     pub database: DatabaseHandler,
+    decoders: HashMap<Uuid, FrameDecoder>,
+    history_decoders: HistoryDecoders,
+    spo2_config: SpO2Config,
+    history_complete: bool,
+    received_history: ReceivedHistory,
 }
 
 impl OpenWhoop {
     pub fn new(database: DatabaseHandler) -> Self {
-        Self { database }
+        Self {
+            database,
+            decoders: HashMap::new(),
+            history_decoders: HistoryDecoders::known().clone(),
+            spo2_config: SpO2Config::default(),
+            history_complete: false,
+            received_history: ReceivedHistory::default(),
+        }
+    }
+
+    pub fn with_spo2_config(self, spo2_config: SpO2Config) -> Self {
+        Self {
+            spo2_config,
+            ..self
+        }
+    }
+
+    /// Historical records with `version` are parsed with `decoder`, replacing known decoder
+    /// for it
+    pub fn with_history_decoder(self, version: u8, decoder: HistoryDecoder) -> Self {
+        Self {
+            history_decoders: self.history_decoders.register(version, decoder),
+            ..self
+        }
+    }
+
+    /// Whether strap reported that all history was sent since this was last called
+    pub fn take_history_complete(&mut self) -> bool {
+        std::mem::take(&mut self.history_complete)
+    }
+
+    /// History readings handled since this was last called
+    pub fn take_received_history(&mut self) -> ReceivedHistory {
+        std::mem::take(&mut self.received_history)
     }
 
     pub async fn store_packet(
@@ -34,42 +81,38 @@ impl OpenWhoop {
     }
 
     pub async fn handle_packet(
-        &self,
+        &mut self,
         packet: packets::Model,
-    ) -> anyhow::Result<Option<WhoopPacket>> {
-        match packet.uuid {
-            DATA_FROM_STRAP => {
-                let packet = WhoopPacket::from_data(packet.bytes)?;
+    ) -> anyhow::Result<Vec<WhoopPacket>> {
+        let mut responses = Vec::new();
 
-                let Ok(data) = WhoopData::from_packet(packet) else {
-                    return Ok(None);
-                };
+        match packet.uuid {
+            DATA_FROM_STRAP | CMD_FROM_STRAP => {
+                let frames = self
+                    .decoders
+                    .entry(packet.uuid)
+                    .or_default()
+                    .decode(&packet.bytes);
 
-                match data {
-                    WhoopData::HistoryReading(HistoryReading {
-                        unix,
-                        bpm,
-                        rr,
-                        activity,
-                    }) => {
-                        self.database
-                            .create_reading(unix, bpm, rr, activity as i64)
-                            .await?;
+                for (frame, data) in frames.into_iter().enumerate() {
+                    let response = self.handle_data(data, packet.id, frame as i16).await?;
+                    if let Some(response) = response {
+                        responses.push(response);
                     }
-                    WhoopData::HistoryMetadata { data, cmd, .. } => match cmd {
-                        MetadataType::HistoryComplete => return Ok(None),
-                        MetadataType::HistoryStart => {}
-                        MetadataType::HistoryEnd => {
-                            let packet = WhoopPacket::history_end(data);
-                            return Ok(Some(packet));
-                        }
-                    },
-                    WhoopData::ConsoleLog { log, .. } => {
-                        trace!(target: "ConsoleLog", "{}", log);
+                }
+            }
+            EVENTS_FROM_STRAP => {
+                let packets = self
+                    .decoders
+                    .entry(packet.uuid)
+                    .or_default()
+                    .decode(&packet.bytes);
+
+                for packet in packets {
+                    match StrapEvent::from_packet(&packet) {
+                        Ok(event) => self.database.create_event(event, packet.data).await?,
+                        Err(error) => warn!("Invalid strap event: {}", error),
                     }
-                    WhoopData::RunAlarm { .. } => {}
-                    WhoopData::Event { .. } => {}
-                    WhoopData::UnknownEvent { .. } => {}
                 }
             }
             _ => {
@@ -77,6 +120,77 @@ impl OpenWhoop {
             }
         }
 
+        Ok(responses)
+    }
+
+    /// `packet_id` is stored packet data was decoded from, `frame` its position in frames decoded
+    /// from that packet
+    async fn handle_data(
+        &mut self,
+        packet: WhoopPacket,
+        packet_id: i32,
+        frame: i16,
+    ) -> anyhow::Result<Option<WhoopPacket>> {
+        let data = match WhoopData::from_packet_with(packet, &self.history_decoders) {
+            Ok(data) => data,
+            Err(WhoopError::UnknownHistoryVersion(version)) => {
+                warn!("No decoder for historical record version: {}", version);
+                return Ok(None);
+            }
+            Err(_) => return Ok(None),
+        };
+
+        match data {
+            WhoopData::HistoryReading(reading) => {
+                let skin_temp = reading.skin_temp();
+                let HistoryReading {
+                    unix,
+                    bpm,
+                    rr,
+                    activity,
+                    ..
+                } = reading;
+
+                self.received_history.add(unix);
+                self.database
+                    .create_reading(unix, bpm, rr, activity as i64, skin_temp)
+                    .await?;
+            }
+            WhoopData::HistoryMetadata { data, cmd, .. } => match cmd {
+                MetadataType::HistoryComplete => {
+                    self.history_complete = true;
+                    return Ok(None);
+                }
+                MetadataType::HistoryStart => {}
+                MetadataType::HistoryEnd => {
+                    let packet = WhoopPacket::history_end(data);
+                    return Ok(Some(packet));
+                }
+            },
+            WhoopData::RealtimeHeartRate(reading) => {
+                self.database.create_realtime_reading(reading).await?;
+            }
+            WhoopData::RealtimeImu(imu) | WhoopData::HistoricalImu(imu) => {
+                self.database
+                    .create_imu_samples(imu, packet_id, frame)
+                    .await?;
+            }
+            WhoopData::RawOptical(optical) => {
+                self.database
+                    .create_ppg_samples(optical, packet_id, frame)
+                    .await?;
+            }
+            WhoopData::ConsoleLog { log, .. } => {
+                trace!(target: "ConsoleLog", "{}", log);
+            }
+            WhoopData::RunAlarm { .. } => {}
+            WhoopData::Event { .. } => {}
+            WhoopData::UnknownEvent { .. } => {}
+            WhoopData::CommandResponse(response) => {
+                info!(target: "CommandResponse", "{:?}", response);
+            }
+        }
+
         Ok(None)
     }
 
@@ -136,6 +250,132 @@ impl OpenWhoop {
         Ok(())
     }
 
+    /// Estimates heart rate from stored raw PPG samples and compares it with readings from strap,
+    /// if `fill_rr` is set readings without RR intervals get intervals from PPG estimate
+    pub async fn ppg_heart_rate(&self, fill_rr: bool) -> anyhow::Result<PpgAgreement> {
+        let samples = self.database.search_ppg(SearchHistory::default()).await?;
+        let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+            return Ok(PpgAgreement::default());
+        };
+
+        let options = SearchHistory {
+            from: Some(first.time - TimeDelta::seconds(1)),
+            to: Some(last.time + TimeDelta::seconds(1)),
+            ..Default::default()
+        };
+
+        let estimates = PpgHeartRate::detect(&samples);
+        let mut history = self.database.search_history(options).await?;
+        let agreement = PpgAgreement::compare(&estimates, &history);
+
+        if fill_rr {
+            let filled = PpgHeartRate::fill_missing_rr(&estimates, &mut history);
+            for i in filled.iter().copied() {
+                let reading = &history[i];
+                self.database
+                    .update_rr(reading.time, reading.rr.clone())
+                    .await?;
+            }
+            info!("Filled RR intervals for {} readings", filled.len());
+        }
+
+        Ok(agreement)
+    }
+
+    /// Recalculates SpO2 series and summary for every stored sleep
+    pub async fn calculate_spo2(&self) -> anyhow::Result<()> {
+        for sleep in self.database.get_sleep_cycles().await? {
+            let spo2 = self.nightly_spo2(sleep.start, sleep.end).await?;
+            let sleep = sleep.with_spo2(&spo2);
+
+            info!(
+                "Sleep {}: {} SpO2 readings, min: {:?}, avg: {:?}",
+                sleep.id,
+                spo2.len(),
+                sleep.min_spo2,
+                sleep.avg_spo2
+            );
+            self.database.create_sleep(sleep).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Recalculates nightly skin temperature and deviation from baseline for every stored sleep
+    pub async fn calculate_skin_temp(&self) -> anyhow::Result<()> {
+        let mut previous = Vec::new();
+
+        for sleep in self.database.get_sleep_cycles().await? {
+            let readings = self
+                .database
+                .search_skin_temp(SearchHistory {
+                    from: Some(sleep.start),
+                    to: Some(sleep.end),
+                    ..Default::default()
+                })
+                .await?;
+
+            let skin_temp = SkinTemperature::calculate(&readings, &previous);
+            let sleep = sleep.with_skin_temp(skin_temp);
+            if let Some(skin_temp) = skin_temp {
+                previous.push(skin_temp.nightly);
+            }
+
+            info!(
+                "Sleep {}: skin temp: {:?}, deviation: {:?}",
+                sleep.id, sleep.skin_temp, sleep.skin_temp_deviation
+            );
+            self.database.create_sleep(sleep).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Skin temperature of sleep with baseline from sleeps stored before it
+    async fn nightly_skin_temp(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> anyhow::Result<Option<SkinTemperature>> {
+        let readings = self
+            .database
+            .search_skin_temp(SearchHistory {
+                from: Some(start),
+                to: Some(end),
+                ..Default::default()
+            })
+            .await?;
+
+        let previous = self
+            .database
+            .get_sleep_cycles()
+            .await?
+            .into_iter()
+            .filter(|sleep| sleep.end < start)
+            .filter_map(|sleep| sleep.skin_temp)
+            .collect::<Vec<_>>();
+
+        Ok(SkinTemperature::calculate(&readings, &previous))
+    }
+
+    async fn nightly_spo2(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> anyhow::Result<Vec<SpO2Reading>> {
+        let options = SearchHistory {
+            from: Some(start),
+            to: Some(end),
+            ..Default::default()
+        };
+
+        let samples = self.database.search_ppg(options).await?;
+        let spo2 = SpO2Reading::detect(&samples, &self.spo2_config);
+        self.database.create_spo2(&spo2).await?;
+
+        Ok(spo2)
+    }
+
     pub async fn detect_sleeps(&self) -> anyhow::Result<()> {
         'a: loop {
             let last_sleep = self.get_latest_sleep().await?;
@@ -181,13 +421,20 @@ impl OpenWhoop {
                                 continue;
                             } else {
                                 // this means that previous sleep was an nap
-                                todo!();
+                                return Err(anyhow::anyhow!(
+                                    "Earlier sleep of {} would have to become nap, which isn't supported",
+                                    this_sleep_id
+                                ));
                             }
                         }
                     }
                 }
 
-                let sleep_cycle = SleepCycle::from_event(sleep, &history);
+                let spo2 = self.nightly_spo2(sleep.start, sleep.end).await?;
+                let skin_temp = self.nightly_skin_temp(sleep.start, sleep.end).await?;
+                let sleep_cycle = SleepCycle::from_event(sleep, &history)
+                    .with_spo2(&spo2)
+                    .with_skin_temp(skin_temp);
 
                 info!(
                     "Detected sleep from {} to {}, duration: {}",
@@ -0,0 +1,36 @@
+use std::{future::Future, pin::Pin};
+
+use btleplug::api::ValueNotification;
+use futures::Stream;
+use uuid::Uuid;
+
+mod ble;
+pub use ble::BleTransport;
+
+mod memory;
+pub use memory::{MemoryStrap, MemoryTransport};
+
+mod replay;
+pub use replay::{RecordedNotification, ReplayTransport};
+
+pub type NotificationStream = Pin<Box<dyn Stream<Item = ValueNotification> + Send>>;
+
+/// Link to strap that [`WhoopDevice`](crate::WhoopDevice) talks through,
+/// characteristics are identified by their uuids from [`whoop::constants`]
+pub trait WhoopTransport: Send + Sync {
+    fn connect(&mut self) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn is_connected(&self) -> impl Future<Output = anyhow::Result<bool>> + Send;
+
+    fn subscribe(&self, characteristic: Uuid) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Writes without waiting for acknowledgement
+    fn write(
+        &self,
+        characteristic: Uuid,
+        data: &[u8],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Stream of notifications on subscribed characteristics received after it was created
+    fn notifications(&self) -> impl Future<Output = anyhow::Result<NotificationStream>> + Send;
+}
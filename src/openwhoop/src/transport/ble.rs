@@ -0,0 +1,66 @@
+use std::collections::BTreeSet;
+
+use btleplug::{
+    api::{CharPropFlags, Characteristic, Peripheral as _, WriteType},
+    platform::Peripheral,
+};
+use uuid::Uuid;
+use whoop::constants::WHOOP_SERVICE;
+
+use super::{NotificationStream, WhoopTransport};
+
+/// Strap connected over Bluetooth LE through `btleplug`
+pub struct BleTransport {
+    peripheral: Peripheral,
+}
+
+impl BleTransport {
+    pub fn new(peripheral: Peripheral) -> Self {
+        Self { peripheral }
+    }
+
+    fn create_char(characteristic: Uuid) -> Characteristic {
+        Characteristic {
+            uuid: characteristic,
+            service_uuid: WHOOP_SERVICE,
+            properties: CharPropFlags::empty(),
+            descriptors: BTreeSet::new(),
+        }
+    }
+}
+
+impl WhoopTransport for BleTransport {
+    async fn connect(&mut self) -> anyhow::Result<()> {
+        self.peripheral.connect().await?;
+        self.peripheral.discover_services().await?;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> anyhow::Result<bool> {
+        let is_connected = self.peripheral.is_connected().await?;
+        Ok(is_connected)
+    }
+
+    async fn subscribe(&self, characteristic: Uuid) -> anyhow::Result<()> {
+        self.peripheral
+            .subscribe(&Self::create_char(characteristic))
+            .await?;
+        Ok(())
+    }
+
+    async fn write(&self, characteristic: Uuid, data: &[u8]) -> anyhow::Result<()> {
+        self.peripheral
+            .write(
+                &Self::create_char(characteristic),
+                data,
+                WriteType::WithoutResponse,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn notifications(&self) -> anyhow::Result<NotificationStream> {
+        let notifications = self.peripheral.notifications().await?;
+        Ok(notifications)
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use btleplug::api::ValueNotification;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use super::{NotificationStream, WhoopTransport};
+
+const NOTIFICATION_CAPACITY: usize = 1024;
+
+/// Transport backed by channels, the other end is [`MemoryStrap`]
+pub struct MemoryTransport {
+    connected: Arc<AtomicBool>,
+    subscribed: Arc<Mutex<BTreeSet<Uuid>>>,
+    notifications: broadcast::Sender<ValueNotification>,
+    writes: mpsc::UnboundedSender<(Uuid, Vec<u8>)>,
+}
+
+/// Strap side of [`MemoryTransport`], receives writes and sends notifications
+pub struct MemoryStrap {
+    connected: Arc<AtomicBool>,
+    subscribed: Arc<Mutex<BTreeSet<Uuid>>>,
+    notifications: broadcast::Sender<ValueNotification>,
+    writes: mpsc::UnboundedReceiver<(Uuid, Vec<u8>)>,
+}
+
+impl MemoryTransport {
+    pub fn pair() -> (Self, MemoryStrap) {
+        let connected = Arc::new(AtomicBool::new(false));
+        let subscribed = Arc::new(Mutex::new(BTreeSet::new()));
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let (writes, received) = mpsc::unbounded_channel();
+
+        let transport = Self {
+            connected: connected.clone(),
+            subscribed: subscribed.clone(),
+            notifications: notifications.clone(),
+            writes,
+        };
+
+        let strap = MemoryStrap {
+            connected,
+            subscribed,
+            notifications,
+            writes: received,
+        };
+
+        (transport, strap)
+    }
+}
+
+impl WhoopTransport for MemoryTransport {
+    async fn connect(&mut self) -> anyhow::Result<()> {
+        self.connected.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> anyhow::Result<bool> {
+        Ok(self.connected.load(Ordering::SeqCst))
+    }
+
+    async fn subscribe(&self, characteristic: Uuid) -> anyhow::Result<()> {
+        self.subscribed
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Subscriptions lock poisoned"))?
+            .insert(characteristic);
+        Ok(())
+    }
+
+    async fn write(&self, characteristic: Uuid, data: &[u8]) -> anyhow::Result<()> {
+        if !self.connected.load(Ordering::SeqCst) {
+            anyhow::bail!("Not connected");
+        }
+
+        self.writes
+            .send((characteristic, data.to_vec()))
+            .map_err(|_| anyhow::anyhow!("Strap was dropped"))
+    }
+
+    async fn notifications(&self) -> anyhow::Result<NotificationStream> {
+        let receiver = self.notifications.subscribe();
+        let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(notification) => return Some((notification, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} notifications", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+impl MemoryStrap {
+    /// Sends notification if transport is subscribed to `characteristic`,
+    /// returns whether it was sent
+    pub fn notify(&self, characteristic: Uuid, value: Vec<u8>) -> bool {
+        let subscribed = self.is_subscribed(characteristic);
+        if subscribed {
+            let _ = self.notifications.send(ValueNotification {
+                uuid: characteristic,
+                value,
+            });
+        }
+
+        subscribed
+    }
+
+    /// Next write made by transport, `None` once transport is dropped
+    pub async fn next_write(&mut self) -> Option<(Uuid, Vec<u8>)> {
+        self.writes.recv().await
+    }
+
+    pub fn is_subscribed(&self, characteristic: Uuid) -> bool {
+        self.subscribed
+            .lock()
+            .map(|subscribed| subscribed.contains(&characteristic))
+            .unwrap_or_default()
+    }
+
+    /// Drops connection, like with BLE subscriptions have to be made again after reconnecting
+    pub fn disconnect(&self) {
+        self.connected.store(false, Ordering::SeqCst);
+        if let Ok(mut subscribed) = self.subscribed.lock() {
+            subscribed.clear();
+        }
+    }
+}
+
+impl Drop for MemoryStrap {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use btleplug::api::ValueNotification;
+use chrono::NaiveDateTime;
+use db_entities::packets;
+use tokio::{sync::mpsc, time::sleep};
+use uuid::Uuid;
+
+use super::{NotificationStream, WhoopTransport};
+
+/// Notifications buffered ahead of consumer, replay waits while buffer is full
+const REPLAY_BUFFER: usize = 64;
+
+/// Notification recorded in `packets` table
+#[derive(Debug, Clone)]
+pub struct RecordedNotification {
+    pub uuid: Uuid,
+    pub value: Vec<u8>,
+    /// Time notification was received, packets stored before it was recorded don't have it
+    pub time: Option<NaiveDateTime>,
+}
+
+impl From<packets::Model> for RecordedNotification {
+    fn from(packet: packets::Model) -> Self {
+        Self {
+            uuid: packet.uuid,
+            value: packet.bytes,
+            time: packet.time,
+        }
+    }
+}
+
+/// Transport that plays back recorded notifications instead of talking to strap.
+///
+/// Playback starts when notification stream is first requested, there is only one stream so
+/// [`WhoopDevice::request`](crate::WhoopDevice::request) doesn't work with it.
+/// Writes are recorded and can be compared with commands sent in original session.
+/// Transport reports it is disconnected once all notifications were consumed
+pub struct ReplayTransport {
+    notifications: Mutex<Option<Vec<RecordedNotification>>>,
+    speed: Option<f64>,
+    connected: Arc<AtomicBool>,
+    writes: Mutex<Vec<(Uuid, Vec<u8>)>>,
+}
+
+impl ReplayTransport {
+    /// `speed` of `1.0` replays with original timing, `2.0` twice as fast, etc.
+    /// Without `speed`, or if notifications don't have time, they are sent one after another
+    pub fn new(notifications: Vec<RecordedNotification>, speed: Option<f64>) -> Self {
+        Self {
+            notifications: Mutex::new(Some(notifications)),
+            speed: speed.filter(|speed| *speed > 0.0),
+            connected: Arc::new(AtomicBool::new(false)),
+            writes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Writes made so far, `(characteristic, data)`
+    pub fn writes(&self) -> Vec<(Uuid, Vec<u8>)> {
+        self.writes
+            .lock()
+            .map(|writes| writes.clone())
+            .unwrap_or_default()
+    }
+
+    fn delay(&self, previous: Option<NaiveDateTime>, next: Option<NaiveDateTime>) -> Duration {
+        let (Some(speed), Some(previous), Some(next)) = (self.speed, previous, next) else {
+            return Duration::ZERO;
+        };
+
+        (next - previous)
+            .to_std()
+            .map(|delay| delay.div_f64(speed))
+            .unwrap_or_default()
+    }
+}
+
+impl WhoopTransport for ReplayTransport {
+    async fn connect(&mut self) -> anyhow::Result<()> {
+        let has_notifications = self
+            .notifications
+            .lock()
+            .map(|notifications| notifications.is_some())
+            .unwrap_or_default();
+        self.connected.store(has_notifications, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> anyhow::Result<bool> {
+        Ok(self.connected.load(Ordering::SeqCst))
+    }
+
+    async fn subscribe(&self, _characteristic: Uuid) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn write(&self, characteristic: Uuid, data: &[u8]) -> anyhow::Result<()> {
+        self.writes
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Writes lock poisoned"))?
+            .push((characteristic, data.to_vec()));
+        Ok(())
+    }
+
+    async fn notifications(&self) -> anyhow::Result<NotificationStream> {
+        let notifications = self
+            .notifications
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Notifications lock poisoned"))?
+            .take();
+
+        let Some(notifications) = notifications else {
+            warn!("Replay was already started, returning empty stream");
+            return Ok(Box::pin(futures::stream::empty()));
+        };
+
+        let delays = notifications
+            .iter()
+            .scan(None, |previous, notification| {
+                let delay = self.delay(*previous, notification.time);
+                *previous = notification.time.or(*previous);
+                Some(delay)
+            })
+            .collect::<Vec<_>>();
+
+        let (sender, receiver) = mpsc::channel(REPLAY_BUFFER);
+        tokio::spawn(async move {
+            for (notification, delay) in notifications.into_iter().zip(delays) {
+                if !delay.is_zero() {
+                    sleep(delay).await;
+                }
+
+                let notification = ValueNotification {
+                    uuid: notification.uuid,
+                    value: notification.value,
+                };
+
+                if sender.send(notification).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let connected = self.connected.clone();
+        let stream = futures::stream::unfold(receiver, move |mut receiver| {
+            let connected = connected.clone();
+            async move {
+                let notification = receiver.recv().await;
+                if notification.is_none() {
+                    connected.store(false, Ordering::SeqCst);
+                }
+
+                Some((notification?, receiver))
+            }
+        });
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,44 @@
+use std::{fmt::Display, str::FromStr};
+
+use uuid::Uuid;
+use whoop::constants::CMD_TO_STRAP;
+
+/// Direction of stored packet, notifications come from strap and commands are written to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketDirection {
+    FromStrap,
+    ToStrap,
+}
+
+impl PacketDirection {
+    /// Direction implied by characteristic packet was sent on
+    pub fn of(characteristic: Uuid) -> Self {
+        match characteristic {
+            CMD_TO_STRAP => Self::ToStrap,
+            _ => Self::FromStrap,
+        }
+    }
+}
+
+impl Display for PacketDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PacketDirection::FromStrap => "from_strap",
+            PacketDirection::ToStrap => "to_strap",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for PacketDirection {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "from_strap" => Ok(PacketDirection::FromStrap),
+            "to_strap" => Ok(PacketDirection::ToStrap),
+            _ => Err(()),
+        }
+    }
+}
@@ -5,6 +5,14 @@ mod m20250111_123805_heart_rate;
 mod m20250126_200014_alter_heart_rate;
 pub mod m20250127_195808_sleep_cycles;
 mod m20250202_085524_activities;
+mod m20250216_101530_events;
+mod m20250218_193012_imu_samples;
+mod m20250222_141845_ppg_samples;
+mod m20250301_092210_spo2;
+mod m20250305_201744_skin_temp;
+mod m20250309_174502_packet_time;
+mod m20250314_090318_packet_direction;
+mod m20250318_184522_sync_sessions;
 
 pub struct Migrator;
 
@@ -17,6 +25,14 @@ impl MigratorTrait for Migrator {
             Box::new(m20250126_200014_alter_heart_rate::Migration),
             Box::new(m20250127_195808_sleep_cycles::Migration),
             Box::new(m20250202_085524_activities::Migration),
+            Box::new(m20250216_101530_events::Migration),
+            Box::new(m20250218_193012_imu_samples::Migration),
+            Box::new(m20250222_141845_ppg_samples::Migration),
+            Box::new(m20250301_092210_spo2::Migration),
+            Box::new(m20250305_201744_skin_temp::Migration),
+            Box::new(m20250309_174502_packet_time::Migration),
+            Box::new(m20250314_090318_packet_direction::Migration),
+            Box::new(m20250318_184522_sync_sessions::Migration),
         ]
     }
 }
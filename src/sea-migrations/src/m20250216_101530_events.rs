@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Events::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Events::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Events::Time).date_time().not_null())
+                    // Sqlite and sea orm doesn't have `u8`
+                    .col(ColumnDef::new(Events::EventId).small_integer().not_null())
+                    .col(ColumnDef::new(Events::Event).string_len(64).not_null())
+                    .col(ColumnDef::new(Events::Raw).binary().not_null())
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx_events_time_event_id")
+                            .col(Events::Time)
+                            .col(Events::EventId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Events::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Events {
+    Table,
+    Id,
+    Time,
+    EventId,
+    Event,
+    Raw,
+}
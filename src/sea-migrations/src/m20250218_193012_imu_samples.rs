@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImuSamples::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ImuSamples::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // packet samples were decoded from, strap sends several batches per second
+                    .col(ColumnDef::new(ImuSamples::PacketId).integer().not_null())
+                    // position of batch in frames decoded from packet
+                    .col(ColumnDef::new(ImuSamples::Frame).small_integer().not_null())
+                    .col(ColumnDef::new(ImuSamples::Time).date_time().not_null())
+                    // position of sample in batch, all samples in batch share same time
+                    .col(
+                        ColumnDef::new(ImuSamples::Sample)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ImuSamples::AccX).small_integer().not_null())
+                    .col(ColumnDef::new(ImuSamples::AccY).small_integer().not_null())
+                    .col(ColumnDef::new(ImuSamples::AccZ).small_integer().not_null())
+                    .col(ColumnDef::new(ImuSamples::GyrX).small_integer().not_null())
+                    .col(ColumnDef::new(ImuSamples::GyrY).small_integer().not_null())
+                    .col(ColumnDef::new(ImuSamples::GyrZ).small_integer().not_null())
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx_imu_samples_packet_frame_sample")
+                            .col(ImuSamples::PacketId)
+                            .col(ImuSamples::Frame)
+                            .col(ImuSamples::Sample),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImuSamples::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ImuSamples {
+    Table,
+    Id,
+    PacketId,
+    Frame,
+    Time,
+    Sample,
+    AccX,
+    AccY,
+    AccZ,
+    GyrX,
+    GyrY,
+    GyrZ,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PpgSamples::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PpgSamples::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // packet samples were decoded from, strap sends several batches per second
+                    .col(ColumnDef::new(PpgSamples::PacketId).integer().not_null())
+                    // position of batch in frames decoded from packet
+                    .col(ColumnDef::new(PpgSamples::Frame).small_integer().not_null())
+                    .col(ColumnDef::new(PpgSamples::Time).date_time().not_null())
+                    // position of sample in batch, all samples in batch share same time
+                    .col(
+                        ColumnDef::new(PpgSamples::Sample)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PpgSamples::SampleRate)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    // Sqlite and sea orm doesn't have `u32`
+                    .col(ColumnDef::new(PpgSamples::Green).big_integer().not_null())
+                    .col(ColumnDef::new(PpgSamples::Red).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(PpgSamples::Infrared)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PpgSamples::Ambient).big_integer().not_null())
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx_ppg_samples_packet_frame_sample")
+                            .col(PpgSamples::PacketId)
+                            .col(PpgSamples::Frame)
+                            .col(PpgSamples::Sample),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PpgSamples::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum PpgSamples {
+    Table,
+    Id,
+    PacketId,
+    Frame,
+    Time,
+    Sample,
+    SampleRate,
+    Green,
+    Red,
+    Infrared,
+    Ambient,
+}
@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Spo2Readings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Spo2Readings::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Spo2Readings::Time)
+                            .date_time()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Spo2Readings::Spo2).double().not_null())
+                    .col(ColumnDef::new(Spo2Readings::Ratio).double().not_null())
+                    .col(ColumnDef::new(Spo2Readings::Quality).double().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SleepCycles::Table)
+                    .add_column(ColumnDef::new(SleepCycles::MinSpo2).small_integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SleepCycles::Table)
+                    .add_column(ColumnDef::new(SleepCycles::AvgSpo2).small_integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SleepCycles::Table)
+                    .drop_column(SleepCycles::AvgSpo2)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SleepCycles::Table)
+                    .drop_column(SleepCycles::MinSpo2)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Spo2Readings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Spo2Readings {
+    Table,
+    Id,
+    Time,
+    Spo2,
+    Ratio,
+    Quality,
+}
+
+#[derive(Iden)]
+enum SleepCycles {
+    Table,
+    MinSpo2,
+    AvgSpo2,
+}
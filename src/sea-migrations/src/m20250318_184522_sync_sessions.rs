@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SyncSessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SyncSessions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SyncSessions::StartedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SyncSessions::UpdatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SyncSessions::FinishedAt).date_time().null())
+                    .col(
+                        ColumnDef::new(SyncSessions::LastAcknowledged)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(SyncSessions::Batches)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SyncSessions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum SyncSessions {
+    Table,
+    Id,
+    StartedAt,
+    UpdatedAt,
+    FinishedAt,
+    LastAcknowledged,
+    Batches,
+}
@@ -0,0 +1,8 @@
+#[macro_use]
+extern crate log;
+
+mod record;
+pub use record::synthetic_record;
+
+mod simulator;
+pub use simulator::{SimulatorConfig, SimulatorOutput, StrapSimulator};
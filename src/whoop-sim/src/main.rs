@@ -0,0 +1,90 @@
+#[macro_use]
+extern crate log;
+
+use clap::Parser;
+use openwhoop::{transport::MemoryTransport, DatabaseHandler, HistoryAck, WhoopDevice};
+use whoop::WhoopPacket;
+use whoop_sim::{SimulatorConfig, StrapSimulator};
+
+/// Downloads history from simulated strap into database
+#[derive(Parser)]
+pub struct SimulatorCli {
+    #[arg(env, long)]
+    pub database_url: String,
+    /// Number of records stored on strap
+    #[arg(long, default_value_t = 1000)]
+    pub records: u32,
+    /// Records sent between two `HistoryEnd` packets
+    #[arg(long, default_value_t = 100)]
+    pub batch_size: u32,
+    /// Drop connection once after this many records were sent
+    #[arg(long)]
+    pub disconnect_after: Option<u32>,
+    /// Acknowledged records aren't trimmed, read pointer goes back to oldest one on disconnect
+    #[arg(long)]
+    pub keep_history: bool,
+    /// Don't acknowledge history batches, strap keeps all records
+    #[arg(long)]
+    pub no_ack: bool,
+    /// Unix time of first record, defaults to `records` seconds ago
+    #[arg(long)]
+    pub start: Option<u32>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .filter_module("sqlx::query", log::LevelFilter::Off)
+        .filter_module("sea_orm_migration::migrator", log::LevelFilter::Off)
+        .init();
+
+    let cli = SimulatorCli::parse();
+    let db_handler = DatabaseHandler::new(cli.database_url).await;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as u32;
+    let config = SimulatorConfig {
+        start: cli.start.unwrap_or(now.saturating_sub(cli.records)),
+        records: cli.records,
+        batch_size: cli.batch_size,
+        disconnect_after: cli.disconnect_after,
+        trim_on_ack: !cli.keep_history,
+        ..Default::default()
+    };
+
+    let (transport, strap) = MemoryTransport::pair();
+    let simulator = tokio::spawn(StrapSimulator::new(config).run(strap));
+    let history_ack = match cli.no_ack {
+        true => HistoryAck::ReadOnly,
+        false => HistoryAck::Acknowledge,
+    };
+    let mut whoop =
+        WhoopDevice::with_transport(transport, db_handler).with_history_ack(history_ack);
+
+    loop {
+        whoop.connect().await?;
+        whoop.initialize().await?;
+        whoop.sync_history().await?;
+
+        if whoop.is_connected().await? {
+            break;
+        }
+
+        warn!("Simulated strap disconnected, reconnecting");
+    }
+
+    whoop
+        .send_command(WhoopPacket::exit_high_freq_sync())
+        .await?;
+
+    drop(whoop);
+    let simulator = simulator.await?;
+    info!(
+        "Sent {} records, {} remaining on strap",
+        simulator.sent(),
+        simulator.remaining()
+    );
+
+    Ok(())
+}
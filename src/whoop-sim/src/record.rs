@@ -0,0 +1,65 @@
+use whoop::{constants::PacketType, WhoopPacket};
+
+/// Record version that [`whoop::HistoryDecoders::default`] has a decoder for
+const RECORD_VERSION: u8 = 5;
+/// Activity word copied from a real record
+const ACTIVITY: u32 = 1285750784;
+/// Tail of a real record without skin temperature
+const TAIL: [u8; 12] = [0x01, 0x0c, 0x02, 0x0c, 0x20, 0, 0, 0, 0, 0, 0, 0x02];
+
+/// `HISTORICAL_DATA` packet in layout of [`whoop::HistoryReadingV2`],
+/// heart rate changes with `sequence` so records can be told apart
+pub fn synthetic_record(sequence: u32, unix: u32) -> WhoopPacket {
+    let bpm = 55 + (sequence % 30) as u8;
+    let rr = 60_000 / u16::from(bpm);
+    let gravity = [0f32, 0.0, 1.0];
+
+    let mut data = Vec::with_capacity(85);
+    data.extend_from_slice(&sequence.to_le_bytes());
+    data.extend_from_slice(&unix.to_le_bytes());
+    data.extend_from_slice(&[0x28, 0x00, 0x80, 0x54, 0x54, 0x01]);
+    data.push(bpm);
+    data.push(1);
+    for rr in [rr, 0, 0, 0] {
+        data.extend_from_slice(&rr.to_le_bytes());
+    }
+    data.extend_from_slice(&ACTIVITY.to_le_bytes());
+
+    data.push(0xff);
+    data.extend_from_slice(&0f32.to_le_bytes());
+    gravity
+        .iter()
+        .for_each(|g| data.extend_from_slice(&g.to_le_bytes()));
+    data.extend_from_slice(&0f32.to_le_bytes());
+    gravity
+        .iter()
+        .for_each(|g| data.extend_from_slice(&g.to_le_bytes()));
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(&TAIL);
+
+    WhoopPacket::new(PacketType::HistoricalData, 0, RECORD_VERSION, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use whoop::{HistoryReading, WhoopData};
+
+    use super::*;
+
+    #[test]
+    fn record_round_trip() {
+        let packet = synthetic_record(31, 1735831144);
+        let packet = WhoopPacket::from_data(&packet.framed_packet()).expect("Invalid frame");
+
+        assert_eq!(
+            WhoopData::from_packet(packet).expect("Invalid record"),
+            WhoopData::HistoryReading(HistoryReading {
+                unix: 1735831144,
+                bpm: 56,
+                rr: vec![1071],
+                activity: ACTIVITY,
+                skin_temp_raw: None,
+            })
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use openwhoop::transport::MemoryStrap;
+use uuid::Uuid;
+use whoop::{
+    constants::{CommandNumber, MetadataType, PacketType, CMD_FROM_STRAP, DATA_FROM_STRAP},
+    WhoopPacket,
+};
+
+use crate::synthetic_record;
+
+/// First two bytes of every command response
+const RESPONSE_HEADER: [u8; 2] = [0x01, 0x00];
+/// Offsets of flags in `GetHelloHarvard` response, same as ones read by [`whoop::CommandResponse`]
+const CHARGING_OFFSET: usize = 7;
+const IS_WORN_OFFSET: usize = 116;
+
+#[derive(Debug, Clone)]
+pub struct SimulatorConfig {
+    /// Unix time of first stored record, records are one second apart
+    pub start: u32,
+    /// Number of stored records
+    pub records: u32,
+    /// Records sent between two `HistoryEnd` metadata packets
+    pub batch_size: u32,
+    /// Connection is dropped once, after this many records were sent
+    pub disconnect_after: Option<u32>,
+    /// Records are trimmed when batch is acknowledged, otherwise acknowledging only moves read
+    /// pointer, which goes back to oldest record on disconnect
+    pub trim_on_ack: bool,
+    /// `SetReadPointer` is answered without moving read pointer, so same batch is sent again
+    pub ignore_read_pointer: bool,
+    /// Battery level in tenths of percent
+    pub battery_level: u16,
+    pub charging: bool,
+    pub is_worn: bool,
+    pub name: String,
+    pub harvard: [u32; 4],
+    pub boylston: [u32; 4],
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            start: 1735831144,
+            records: 100,
+            batch_size: 20,
+            disconnect_after: None,
+            trim_on_ack: true,
+            ignore_read_pointer: false,
+            battery_level: 850,
+            charging: false,
+            is_worn: true,
+            name: "WHOOP SIM".to_owned(),
+            harvard: [41, 16, 6, 0],
+            boylston: [17, 2, 2, 0],
+        }
+    }
+}
+
+/// What strap does in reaction to a command
+#[derive(Debug, Clone)]
+pub enum SimulatorOutput {
+    Notify(Uuid, WhoopPacket),
+    Disconnect,
+}
+
+/// Strap side of the protocol.
+///
+/// History is sent in batches, every batch ends with `HistoryEnd` whose data is the trim cursor.
+/// Records are trimmed only after client answers with `HistoricalDataResult` carrying that cursor,
+/// so records of a batch interrupted by disconnect are sent again on next sync,
+/// unless client moves read pointer with `SetReadPointer`
+pub struct StrapSimulator {
+    config: SimulatorConfig,
+    clock: u32,
+    /// Sequence of first record that wasn't trimmed
+    trimmed: u32,
+    /// Sequence of next record to send
+    read_pointer: u32,
+    /// Trim cursor of last `HistoryEnd`, waiting to be confirmed
+    pending_trim: Option<u32>,
+    sent: u32,
+    received: Vec<WhoopPacket>,
+}
+
+impl StrapSimulator {
+    pub fn new(config: SimulatorConfig) -> Self {
+        Self {
+            clock: config.start + config.records,
+            config,
+            trimmed: 0,
+            read_pointer: 0,
+            pending_trim: None,
+            sent: 0,
+            received: Vec::new(),
+        }
+    }
+
+    /// Records that weren't trimmed yet
+    pub fn remaining(&self) -> u32 {
+        self.config.records - self.trimmed
+    }
+
+    /// Records sent so far, including ones sent more than once
+    pub fn sent(&self) -> u32 {
+        self.sent
+    }
+
+    pub fn clock(&self) -> u32 {
+        self.clock
+    }
+
+    /// Commands received so far
+    pub fn received(&self) -> &[WhoopPacket] {
+        &self.received
+    }
+
+    /// Answers commands written by transport until it is dropped
+    pub async fn run(mut self, mut strap: MemoryStrap) -> Self {
+        while let Some((characteristic, frame)) = strap.next_write().await {
+            let packet = match WhoopPacket::from_data(&frame) {
+                Ok(packet) => packet,
+                Err(error) => {
+                    warn!("Invalid frame on {}: {}", characteristic, error);
+                    continue;
+                }
+            };
+
+            for output in self.handle_command(packet) {
+                match output {
+                    SimulatorOutput::Notify(characteristic, packet) => {
+                        strap.notify(characteristic, packet.framed_packet());
+                    }
+                    SimulatorOutput::Disconnect => strap.disconnect(),
+                }
+            }
+        }
+
+        self
+    }
+
+    pub fn handle_command(&mut self, packet: WhoopPacket) -> Vec<SimulatorOutput> {
+        let mut outputs = Vec::new();
+        let Some(command) = CommandNumber::from_u8(packet.cmd) else {
+            warn!("Unknown command: {}", packet.cmd);
+            self.received.push(packet);
+            return outputs;
+        };
+
+        let payload = match command {
+            CommandNumber::GetClock => self.clock.to_le_bytes().to_vec(),
+            CommandNumber::SetClock => {
+                if let Some(unix) = packet.data.first_chunk::<4>() {
+                    self.clock = u32::from_le_bytes(*unix);
+                }
+                Vec::new()
+            }
+            CommandNumber::GetBatteryLevel => self.config.battery_level.to_le_bytes().to_vec(),
+            CommandNumber::GetHelloHarvard => {
+                let mut data = vec![0; IS_WORN_OFFSET + 1];
+                data[CHARGING_OFFSET] = self.config.charging.into();
+                data[IS_WORN_OFFSET] = self.config.is_worn.into();
+                data.split_off(RESPONSE_HEADER.len())
+            }
+            CommandNumber::GetAdvertisingNameHarvard => {
+                let mut data = vec![self.config.name.len() as u8];
+                data.extend_from_slice(self.config.name.as_bytes());
+                data
+            }
+            CommandNumber::ReportVersionInfo => {
+                let mut data = vec![0];
+                for part in self.config.harvard.iter().chain(&self.config.boylston) {
+                    data.extend_from_slice(&part.to_le_bytes());
+                }
+                data
+            }
+            CommandNumber::GetDataRange => {
+                let mut data = self.trimmed.to_le_bytes().to_vec();
+                data.extend_from_slice(&self.config.records.to_le_bytes());
+                data
+            }
+            _ => Vec::new(),
+        };
+
+        let mut data = RESPONSE_HEADER.to_vec();
+        data.extend(payload);
+        outputs.push(SimulatorOutput::Notify(
+            CMD_FROM_STRAP,
+            WhoopPacket::new(PacketType::CommandResponse, packet.seq, packet.cmd, data),
+        ));
+
+        match command {
+            CommandNumber::SendHistoricalData => {
+                self.pending_trim = None;
+                outputs.push(self.metadata(MetadataType::HistoryStart, 0));
+                self.send_batch(&mut outputs);
+            }
+            CommandNumber::HistoricalDataResult => {
+                let cursor = Self::pointer(&packet);
+                match (self.pending_trim, cursor) {
+                    (Some(pending), Some(cursor)) if pending == cursor => {
+                        self.read_pointer = cursor;
+                        if self.config.trim_on_ack {
+                            self.trimmed = cursor;
+                        }
+                        self.pending_trim = None;
+                        self.send_batch(&mut outputs);
+                    }
+                    _ => warn!(
+                        "Unexpected trim cursor: {:?}, expected: {:?}",
+                        cursor, self.pending_trim
+                    ),
+                }
+            }
+            CommandNumber::SetReadPointer if self.config.ignore_read_pointer => {}
+            CommandNumber::SetReadPointer => match Self::pointer(&packet) {
+                Some(pointer) if (self.trimmed..=self.config.records).contains(&pointer) => {
+                    self.read_pointer = pointer;
+                    self.pending_trim = None;
+                }
+                pointer => warn!("Read pointer out of stored range: {:?}", pointer),
+            },
+            CommandNumber::AbortHistoricalTransmits => self.pending_trim = None,
+            _ => {}
+        }
+
+        self.received.push(packet);
+        outputs
+    }
+
+    fn send_batch(&mut self, outputs: &mut Vec<SimulatorOutput>) {
+        if self.read_pointer >= self.config.records {
+            outputs.push(self.metadata(MetadataType::HistoryComplete, self.read_pointer));
+            return;
+        }
+
+        let end = (self.read_pointer + self.config.batch_size).min(self.config.records);
+        for sequence in self.read_pointer..end {
+            if self.config.disconnect_after == Some(self.sent) {
+                self.config.disconnect_after = None;
+                self.read_pointer = self.trimmed;
+                outputs.push(SimulatorOutput::Disconnect);
+                return;
+            }
+
+            let record = synthetic_record(sequence, self.config.start + sequence);
+            outputs.push(SimulatorOutput::Notify(DATA_FROM_STRAP, record));
+            self.sent += 1;
+        }
+
+        self.pending_trim = Some(end);
+        outputs.push(self.metadata(MetadataType::HistoryEnd, end));
+    }
+
+    /// Pointer in `HistoricalDataResult` and `SetReadPointer`, layout: `[1][pointer: u32][padding: 4]`
+    fn pointer(packet: &WhoopPacket) -> Option<u32> {
+        packet
+            .data
+            .get(1..5)
+            .and_then(|pointer| pointer.try_into().ok())
+            .map(u32::from_le_bytes)
+    }
+
+    /// Layout: `[unix: u32][padding: 6][data: u32]`
+    fn metadata(&self, metadata: MetadataType, data: u32) -> SimulatorOutput {
+        let mut packet = self.clock.to_le_bytes().to_vec();
+        packet.extend_from_slice(&[0; 6]);
+        packet.extend_from_slice(&data.to_le_bytes());
+
+        SimulatorOutput::Notify(
+            DATA_FROM_STRAP,
+            WhoopPacket::new(PacketType::Metadata, 0, metadata.as_u8(), packet),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openwhoop::{transport::MemoryTransport, DatabaseHandler, RequestPolicy, WhoopDevice};
+    use whoop::CommandResponse;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn answer_requests() {
+        let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+        let (transport, strap) = MemoryTransport::pair();
+        let simulator = tokio::spawn(StrapSimulator::new(SimulatorConfig::default()).run(strap));
+
+        let mut device = WhoopDevice::with_transport(transport, db)
+            .with_request_policy(RequestPolicy::default());
+        device.connect().await.expect("Unable to connect");
+        device.initialize().await.expect("Unable to initialize");
+
+        assert_eq!(
+            device.request(WhoopPacket::get_clock()).await.ok(),
+            Some(CommandResponse::Clock {
+                unix: 1735831144 + 100
+            })
+        );
+        assert_eq!(
+            device.request(WhoopPacket::hello_harvard()).await.ok(),
+            Some(CommandResponse::HelloHarvard {
+                charging: false,
+                is_worn: true
+            })
+        );
+        assert_eq!(
+            device.request(WhoopPacket::get_name()).await.ok(),
+            Some(CommandResponse::AdvertisingName {
+                name: "WHOOP SIM".to_owned()
+            })
+        );
+        assert_eq!(
+            device.request(WhoopPacket::version_info()).await.ok(),
+            Some(CommandResponse::VersionInfo {
+                harvard: "41.16.6.0".to_owned(),
+                boylston: "17.2.2.0".to_owned()
+            })
+        );
+
+        drop(device);
+        let simulator = simulator.await.expect("Simulator failed");
+        assert_eq!(simulator.received().len(), 5);
+    }
+}
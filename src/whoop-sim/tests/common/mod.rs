@@ -0,0 +1,73 @@
+// Not every test binary uses every helper
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use openwhoop::{transport::MemoryTransport, DatabaseHandler, SearchHistory, WhoopDevice};
+use tokio::task::JoinHandle;
+use whoop::constants::CommandNumber;
+use whoop_sim::{SimulatorConfig, StrapSimulator};
+
+/// Step paused clock is moved forward by while database is idle
+const CLOCK_STEP: Duration = Duration::from_millis(1);
+
+/// Opens database for tests that run with paused clock.
+///
+/// Sqlite runs queries on its own thread, so runtime would look idle and auto-advance paused
+/// clock past timeout of connection pool while waiting for them. Clock is moved forward by
+/// spawned task instead, and only while no query runs
+pub async fn database() -> DatabaseHandler {
+    tokio::time::resume();
+    let db = DatabaseHandler::new("sqlite::memory:".to_owned()).await;
+    tokio::time::pause();
+
+    let idle = db.clone();
+    tokio::spawn(async move {
+        loop {
+            match idle.is_idle() {
+                true => tokio::time::advance(CLOCK_STEP).await,
+                false => tokio::task::yield_now().await,
+            }
+        }
+    });
+
+    db
+}
+
+pub async fn connect(
+    config: SimulatorConfig,
+) -> (WhoopDevice<MemoryTransport>, JoinHandle<StrapSimulator>) {
+    let db = database().await;
+    reconnect(StrapSimulator::new(config), db).await
+}
+
+/// Connects new device to existing simulator, like next run of openwhoop would
+pub async fn reconnect(
+    simulator: StrapSimulator,
+    db: DatabaseHandler,
+) -> (WhoopDevice<MemoryTransport>, JoinHandle<StrapSimulator>) {
+    let (transport, strap) = MemoryTransport::pair();
+    let simulator = tokio::spawn(simulator.run(strap));
+
+    let mut device = WhoopDevice::with_transport(transport, db);
+    device.connect().await.expect("Unable to connect");
+    device.initialize().await.expect("Unable to initialize");
+    (device, simulator)
+}
+
+pub async fn stored_history(device: &WhoopDevice<MemoryTransport>) -> usize {
+    device
+        .database()
+        .search_history(SearchHistory::default())
+        .await
+        .expect("Unable to read history")
+        .len()
+}
+
+pub fn received_count(simulator: &StrapSimulator, command: CommandNumber) -> usize {
+    simulator
+        .received()
+        .iter()
+        .filter(|packet| packet.cmd == command.as_u8())
+        .count()
+}
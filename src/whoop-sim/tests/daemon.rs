@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use openwhoop::{
+    daemon::{Daemon, DaemonConfig, DaemonState},
+    transport::MemoryTransport,
+    SearchHistory,
+};
+use tokio::sync::oneshot;
+use whoop::constants::CommandNumber;
+use whoop_sim::{SimulatorConfig, StrapSimulator};
+
+mod common;
+
+#[tokio::test(start_paused = true)]
+async fn daemon_syncs_until_shutdown() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        ..Default::default()
+    };
+    let (transport, strap) = MemoryTransport::pair();
+    let simulator = tokio::spawn(StrapSimulator::new(config).run(strap));
+    let db = common::database().await;
+    let status_file =
+        std::env::temp_dir().join(format!("whoop-sim-daemon-{}.json", std::process::id()));
+
+    // Strap is in range for first scan only, daemon is stopped when it scans again
+    let mut transport = Some(transport);
+    let (stop, stopped) = oneshot::channel();
+    let mut stop = Some(stop);
+    let find_strap = || {
+        let transport = transport.take();
+        if transport.is_none() {
+            stop.take().map(|stop| stop.send(()));
+        }
+
+        async move {
+            match transport {
+                Some(transport) => Ok(transport),
+                None => std::future::pending().await,
+            }
+        }
+    };
+
+    let mut daemon = Daemon::new(
+        db.clone(),
+        DaemonConfig {
+            interval: Duration::from_millis(10),
+            status_file: Some(status_file.clone()),
+            ..Default::default()
+        },
+    );
+    daemon
+        .run(find_strap, async {
+            let _ = stopped.await;
+        })
+        .await
+        .expect("Daemon failed");
+
+    let status = daemon.status();
+    assert_eq!(status.state, DaemonState::Stopped);
+    assert_eq!((status.syncs, status.failed_syncs), (1, 0));
+    let written = std::fs::read_to_string(&status_file).expect("Status not written");
+    std::fs::remove_file(&status_file).expect("Unable to remove status");
+    assert!(written.contains(r#""state": "stopped""#));
+
+    let history = db
+        .search_history(SearchHistory::default())
+        .await
+        .expect("Unable to read history");
+    assert_eq!(history.len(), 50);
+
+    let simulator = simulator.await.expect("Simulator failed");
+    assert_eq!(simulator.remaining(), 0);
+    let exited = common::received_count(&simulator, CommandNumber::ExitHighFreqSync);
+    assert!(exited > 0);
+}
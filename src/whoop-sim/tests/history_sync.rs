@@ -0,0 +1,277 @@
+//! History sync against simulated strap, with disconnects, checkpoints, acknowledge modes and
+//! progress reporting
+
+use std::time::Duration;
+
+use openwhoop::{HistoryAck, ReconnectPolicy};
+use tokio::sync::mpsc;
+use whoop::constants::CommandNumber;
+use whoop_sim::SimulatorConfig;
+
+mod common;
+use common::{connect, received_count, reconnect, stored_history};
+
+#[tokio::test(start_paused = true)]
+async fn download_history() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        ..Default::default()
+    };
+    let (mut device, simulator) = connect(config).await;
+
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 50);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    assert_eq!(simulator.remaining(), 0);
+    assert_eq!(simulator.sent(), 50);
+    // Without checkpoint or progress reporting there is nothing to ask strap for
+    assert_eq!(received_count(&simulator, CommandNumber::GetDataRange), 0);
+    assert_eq!(received_count(&simulator, CommandNumber::SetReadPointer), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn interrupted_batch_is_not_trimmed() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        disconnect_after: Some(25),
+        ..Default::default()
+    };
+    let (mut device, simulator) = connect(config).await;
+
+    device.sync_history().await.expect("Sync failed");
+    assert!(!device.is_connected().await.expect("Transport failed"));
+    assert_eq!(stored_history(&device).await, 25);
+
+    device.connect().await.expect("Unable to reconnect");
+    device.initialize().await.expect("Unable to initialize");
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 50);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    assert_eq!(simulator.remaining(), 0);
+    // Second batch was interrupted after 5 records and sent again
+    assert_eq!(simulator.sent(), 55);
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconnect_during_sync() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        disconnect_after: Some(25),
+        ..Default::default()
+    };
+    let (device, simulator) = connect(config).await;
+    let mut device = device.with_reconnect_policy(ReconnectPolicy {
+        initial_delay: Duration::from_millis(10),
+        ..Default::default()
+    });
+
+    device.sync_history().await.expect("Sync failed");
+    assert!(device.is_connected().await.expect("Transport failed"));
+    assert_eq!(stored_history(&device).await, 50);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    assert_eq!(simulator.remaining(), 0);
+    // High frequency sync was entered again after reconnecting
+    let entered = received_count(&simulator, CommandNumber::EnterHighFreqSync);
+    assert_eq!(entered, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn resume_from_checkpoint() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        disconnect_after: Some(25),
+        trim_on_ack: false,
+        ..Default::default()
+    };
+    let (mut device, simulator) = connect(config).await;
+    let db = device.database().clone();
+
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 25);
+    assert_eq!(db.last_sync_checkpoint().await.ok(), Some(Some(20)));
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    let (mut device, simulator) = reconnect(simulator, db).await;
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 50);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    // Nothing was trimmed, but first batch wasn't sent again
+    assert_eq!(simulator.remaining(), 50);
+    assert_eq!(simulator.sent(), 55);
+    assert_eq!(received_count(&simulator, CommandNumber::SetReadPointer), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn sync_without_resume() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        disconnect_after: Some(25),
+        trim_on_ack: false,
+        ..Default::default()
+    };
+    let (mut device, simulator) = connect(config).await;
+    let db = device.database().clone();
+    device.sync_history().await.expect("Sync failed");
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    let (device, simulator) = reconnect(simulator, db).await;
+    let mut device = device.with_resume(false);
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 50);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    // Second sync started from oldest stored record
+    assert_eq!(simulator.sent(), 75);
+    assert_eq!(received_count(&simulator, CommandNumber::GetDataRange), 0);
+    assert_eq!(received_count(&simulator, CommandNumber::SetReadPointer), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn read_only_sync() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        ..Default::default()
+    };
+    let (device, simulator) = connect(config).await;
+    let mut device = device.with_history_ack(HistoryAck::ReadOnly);
+
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 50);
+    let checkpoint = device.database().last_sync_checkpoint().await;
+    assert_eq!(checkpoint.expect("Unable to read checkpoint"), None);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    assert_eq!(simulator.remaining(), 50);
+    assert_eq!(simulator.sent(), 50);
+    let acknowledged = received_count(&simulator, CommandNumber::HistoricalDataResult);
+    assert_eq!(acknowledged, 0);
+    assert_eq!(received_count(&simulator, CommandNumber::SetReadPointer), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn read_only_sync_with_stuck_read_pointer() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        ignore_read_pointer: true,
+        ..Default::default()
+    };
+    let (device, simulator) = connect(config).await;
+    let mut device = device.with_history_ack(HistoryAck::ReadOnly);
+
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 20);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    // First batch was sent again once, then transmission was aborted
+    assert_eq!(simulator.sent(), 40);
+    assert_eq!(received_count(&simulator, CommandNumber::SetReadPointer), 1);
+    let aborted = received_count(&simulator, CommandNumber::AbortHistoricalTransmits);
+    assert_eq!(aborted, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn abort_sync() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        ..Default::default()
+    };
+    let (device, simulator) = connect(config).await;
+    let mut device = device.with_history_ack(HistoryAck::Abort);
+
+    device.sync_history().await.expect("Sync failed");
+    assert_eq!(stored_history(&device).await, 20);
+
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    assert_eq!(simulator.remaining(), 50);
+    let acknowledged = received_count(&simulator, CommandNumber::HistoricalDataResult);
+    assert_eq!(acknowledged, 0);
+    let aborted = received_count(&simulator, CommandNumber::AbortHistoricalTransmits);
+    assert_eq!(aborted, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn sync_progress() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        ..Default::default()
+    };
+    let (device, simulator) = connect(config).await;
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let mut device = device.with_progress(move |progress| {
+        let _ = sender.send(progress.clone());
+    });
+
+    device.sync_history().await.expect("Sync failed");
+    drop(device);
+    simulator.await.expect("Simulator failed");
+
+    let mut progress = Vec::new();
+    while let Some(event) = events.recv().await {
+        progress.push(event);
+    }
+
+    // Range is reported before first batch, then every batch and completion
+    let positions = progress
+        .iter()
+        .map(|progress| progress.position)
+        .collect::<Vec<_>>();
+    assert_eq!(positions, [None, Some(20), Some(40), Some(50), Some(50)]);
+    assert_eq!(progress[0].range, Some(0..50));
+    assert_eq!(progress[1].fraction(), Some(0.4));
+    assert_eq!(progress[1].received.records, 20);
+
+    let last = progress.last().expect("No progress reported");
+    assert!(last.complete);
+    assert_eq!(last.batches, 3);
+    assert_eq!(last.received.records, 50);
+    assert_eq!(last.eta(), Some(Duration::ZERO));
+}
+
+#[tokio::test(start_paused = true)]
+async fn sync_progress_without_resume() {
+    let config = SimulatorConfig {
+        records: 50,
+        batch_size: 20,
+        ..Default::default()
+    };
+    let (device, simulator) = connect(config).await;
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let mut device = device.with_resume(false).with_progress(move |progress| {
+        let _ = sender.send(progress.clone());
+    });
+
+    device.sync_history().await.expect("Sync failed");
+    drop(device);
+    let simulator = simulator.await.expect("Simulator failed");
+    assert_eq!(received_count(&simulator, CommandNumber::GetDataRange), 1);
+    assert_eq!(received_count(&simulator, CommandNumber::SetReadPointer), 0);
+
+    let first = events.recv().await.expect("No progress reported");
+    assert_eq!(first.range, Some(0..50));
+    let second = events.recv().await.expect("No batch progress reported");
+    assert_eq!(second.fraction(), Some(0.4));
+    assert!(second.eta().is_some());
+}
@@ -0,0 +1,208 @@
+use std::{
+    error::Error,
+    io::{stdin, BufRead},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::Parser;
+use serde::Serialize;
+use whoop::{constants::PacketType, FrameCheck, WhoopData, WhoopPacket};
+
+const SOF: u8 = 0xaa;
+
+/// Validates and decodes WHOOP frames given as hex
+#[derive(Parser)]
+pub struct DecodeCli {
+    /// Hex encoded frames, read from `--file` or stdin (one per line) if none are given
+    pub hex: Vec<String>,
+    /// File with one hex encoded frame per line, lines starting with `#` are skipped
+    #[arg(long, short)]
+    pub file: Option<PathBuf>,
+    /// Print result of every input as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct DecodedInput {
+    input: String,
+    /// Hex decoding error, input isn't checked further if it is set
+    error: Option<String>,
+    frames: Vec<DecodedFrame>,
+    /// Hex of bytes after last valid frame
+    unconsumed: String,
+}
+
+#[derive(Serialize)]
+struct DecodedFrame {
+    checks: Vec<FrameCheck>,
+    packet: Option<DecodedPacket>,
+    data: Option<WhoopData>,
+    /// Why packet couldn't be decoded to [`WhoopData`]
+    data_error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DecodedPacket {
+    packet_type: PacketType,
+    seq: u8,
+    cmd: u8,
+    data: String,
+}
+
+impl DecodedInput {
+    fn passed(&self) -> bool {
+        self.error.is_none()
+            && self
+                .frames
+                .iter()
+                .all(|frame| frame.checks.iter().all(|check| check.passed))
+    }
+}
+
+/// Separators that are common in copied hex: spaces, colons and `0x` prefix
+fn parse_hex(input: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    let input = input.trim();
+    let input = input.strip_prefix("0x").unwrap_or(input);
+    let cleaned = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':')
+        .collect::<String>();
+
+    hex::decode(cleaned)
+}
+
+fn decode(input: &str) -> DecodedInput {
+    let mut decoded = DecodedInput {
+        input: input.trim().to_owned(),
+        error: None,
+        frames: Vec::new(),
+        unconsumed: String::new(),
+    };
+
+    let bytes = match parse_hex(input) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            decoded.error = Some(error.to_string());
+            return decoded;
+        }
+    };
+
+    let mut rest = bytes;
+    loop {
+        let report = WhoopPacket::check_frame(&rest);
+        let (data, data_error) = match report.packet.clone().map(WhoopData::from_packet) {
+            Some(Ok(data)) => (Some(data), None),
+            Some(Err(error)) => (None, Some(error.to_string())),
+            None => (None, None),
+        };
+
+        let valid = report.packet.is_some();
+        decoded.frames.push(DecodedFrame {
+            checks: report.checks,
+            packet: report.packet.map(|packet| DecodedPacket {
+                packet_type: packet.packet_type,
+                seq: packet.seq,
+                cmd: packet.cmd,
+                data: hex::encode(packet.data),
+            }),
+            data,
+            data_error,
+        });
+
+        // Only bytes after valid frame that look like start of another frame are decoded further
+        if !valid {
+            decoded.unconsumed = hex::encode(&rest);
+            break;
+        }
+
+        rest = report.unconsumed;
+        if rest.first() != Some(&SOF) {
+            decoded.unconsumed = hex::encode(&rest);
+            break;
+        }
+    }
+
+    decoded
+}
+
+fn print_text(decoded: &DecodedInput) {
+    println!("Input: {}", decoded.input);
+    if let Some(error) = &decoded.error {
+        println!("  Invalid hex: {}", error);
+        return;
+    }
+
+    for (index, frame) in decoded.frames.iter().enumerate() {
+        println!("  Frame {}:", index + 1);
+        for check in &frame.checks {
+            let mark = if check.passed { "ok  " } else { "FAIL" };
+            println!("    [{}] {:?}: {}", mark, check.step, check.detail);
+        }
+
+        if let Some(packet) = &frame.packet {
+            println!(
+                "    Type: {:?}, Seq: {}, Cmd: {}, Payload ({} bytes): {}",
+                packet.packet_type,
+                packet.seq,
+                packet.cmd,
+                packet.data.len() / 2,
+                packet.data
+            );
+        }
+
+        match (&frame.data, &frame.data_error) {
+            (Some(data), _) => {
+                for line in format!("{:#?}", data).lines() {
+                    println!("    {}", line);
+                }
+            }
+            (None, Some(error)) => println!("    Data not decoded: {}", error),
+            (None, None) => {}
+        }
+    }
+
+    if !decoded.unconsumed.is_empty() {
+        println!(
+            "  Unconsumed ({} bytes): {}",
+            decoded.unconsumed.len() / 2,
+            decoded.unconsumed
+        );
+    }
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let cli = DecodeCli::parse();
+
+    let inputs = match (cli.hex.is_empty(), &cli.file) {
+        (false, _) => cli.hex,
+        (true, Some(file)) => std::fs::read_to_string(file)?
+            .lines()
+            .map(str::to_owned)
+            .collect(),
+        (true, None) => stdin().lock().lines().collect::<Result<_, _>>()?,
+    };
+
+    let mut passed = true;
+    for input in inputs {
+        if input.trim().is_empty() || input.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let decoded = decode(&input);
+        passed &= decoded.passed();
+
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&decoded)?);
+        } else {
+            print_text(&decoded);
+        }
+    }
+
+    Ok(if passed {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
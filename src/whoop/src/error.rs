@@ -14,5 +14,7 @@ pub enum WhoopError {
     InvalidMetadataType(u8),
     InvalidCommandType(u8),
     InvalidConsoleLog,
+    /// Historical record version (`cmd` of packet) without registered decoder
+    UnknownHistoryVersion(u8),
     Unimplemented,
 }
@@ -0,0 +1,204 @@
+use crate::{constants::PacketType, WhoopPacket};
+
+/// Step of frame validation, in order they are checked by [`WhoopPacket::from_data`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+#[cfg_attr(feature = "cli", serde(rename_all = "snake_case"))]
+pub enum FrameStep {
+    Sof,
+    HeaderCrc8,
+    Length,
+    DataCrc32,
+    PacketType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+pub struct FrameCheck {
+    pub step: FrameStep,
+    pub passed: bool,
+    /// What was expected and found, e.g. `expected 0x29, calculated 0x29`
+    pub detail: String,
+}
+
+/// Result of checking one frame step by step, unlike [`WhoopPacket::from_data`] it reports every
+/// step that passed and where frame ends, so frames followed by other bytes are still decoded
+#[derive(Debug, Clone)]
+pub struct FrameReport {
+    /// Checks that were made, validation stops at first failed one
+    pub checks: Vec<FrameCheck>,
+    pub packet: Option<WhoopPacket>,
+    /// Bytes after the end of frame, everything after SOF if frame is invalid
+    pub unconsumed: Vec<u8>,
+}
+
+impl FrameReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+impl WhoopPacket {
+    /// Validates frame at the start of `data` step by step
+    pub fn check_frame(data: &[u8]) -> FrameReport {
+        let mut checks = Vec::new();
+        let frame = Self::validate_frame(data, |step, passed, detail| {
+            checks.push(FrameCheck {
+                step,
+                passed,
+                detail: detail(),
+            });
+        });
+
+        let (packet, unconsumed) = match frame {
+            Ok((packet, length)) => (Some(packet), &data[length..]),
+            Err(_) => (None, data.get(1..).unwrap_or_default()),
+        };
+
+        FrameReport {
+            checks,
+            packet,
+            unconsumed: unconsumed.to_vec(),
+        }
+    }
+
+    /// Steps shared by [`WhoopPacket::check_frame`] and [`WhoopPacket::from_data`].
+    ///
+    /// `check` is called with result of every step until one fails, `detail` describes what was
+    /// expected and found. Returns packet and length of whole frame, or step that failed
+    pub(crate) fn validate_frame(
+        data: &[u8],
+        mut check: impl FnMut(FrameStep, bool, &dyn Fn() -> String),
+    ) -> Result<(Self, usize), FrameStep> {
+        let mut step = |step, passed, detail: &dyn Fn() -> String| {
+            check(step, passed, detail);
+            passed.then_some(()).ok_or(step)
+        };
+
+        let Some(&sof) = data.first() else {
+            step(FrameStep::Sof, false, &|| "no data".to_owned())?;
+            return Err(FrameStep::Sof);
+        };
+        step(FrameStep::Sof, sof == Self::SOF, &|| {
+            format!("expected {:#04x}, found {:#04x}", Self::SOF, sof)
+        })?;
+
+        let Some(header) = data.get(..Self::HEADER_LEN) else {
+            step(FrameStep::HeaderCrc8, false, &|| {
+                format!(
+                    "header needs {} bytes, found {}",
+                    Self::HEADER_LEN,
+                    data.len()
+                )
+            })?;
+            return Err(FrameStep::HeaderCrc8);
+        };
+        let calculated = Self::crc8(&header[1..3]);
+        step(FrameStep::HeaderCrc8, calculated == header[3], &|| {
+            format!(
+                "expected {:#04x}, calculated {:#04x}",
+                header[3], calculated
+            )
+        })?;
+
+        let length = usize::from(u16::from_le_bytes([header[1], header[2]]));
+        let available = data.len() - Self::HEADER_LEN;
+        step(
+            FrameStep::Length,
+            (Self::MIN_LENGTH..=available).contains(&length),
+            &|| {
+                format!(
+                    "declared {}, available {}, minimum {}",
+                    length,
+                    available,
+                    Self::MIN_LENGTH
+                )
+            },
+        )?;
+
+        let (body, crc32) = data[Self::HEADER_LEN..Self::HEADER_LEN + length].split_at(length - 4);
+        let expected = u32::from_le_bytes([crc32[0], crc32[1], crc32[2], crc32[3]]);
+        let calculated = Self::crc32(body);
+        step(FrameStep::DataCrc32, calculated == expected, &|| {
+            format!(
+                "expected {:#010x}, calculated {:#010x}",
+                expected, calculated
+            )
+        })?;
+
+        let packet_type = PacketType::from_u8(body[0]);
+        step(
+            FrameStep::PacketType,
+            packet_type.is_some(),
+            &|| match packet_type {
+                Some(packet_type) => format!("{:?} ({})", packet_type, body[0]),
+                None => format!("unknown type {}", body[0]),
+            },
+        )?;
+
+        let packet = Self {
+            packet_type: packet_type.ok_or(FrameStep::PacketType)?,
+            seq: body[1],
+            cmd: body[2],
+            data: body[3..].to_vec(),
+        };
+        Ok((packet, Self::HEADER_LEN + length))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(report: &FrameReport) -> Vec<(FrameStep, bool)> {
+        report
+            .checks
+            .iter()
+            .map(|check| (check.step, check.passed))
+            .collect()
+    }
+
+    #[test]
+    fn valid_frame_with_trailing_bytes() {
+        let mut data = WhoopPacket::get_clock().framed_packet();
+        data.extend_from_slice(&[0x01, 0x02]);
+
+        let report = WhoopPacket::check_frame(&data);
+        assert!(report.passed());
+        assert_eq!(report.checks.len(), 5);
+        assert_eq!(report.unconsumed, vec![0x01, 0x02]);
+
+        let packet = report.packet.expect("Frame is valid");
+        assert_eq!(packet.packet_type, PacketType::Command);
+        assert_eq!(packet.cmd, WhoopPacket::get_clock().cmd);
+    }
+
+    #[test]
+    fn stops_at_first_failed_step() {
+        let mut data = WhoopPacket::get_clock().framed_packet();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+
+        let report = WhoopPacket::check_frame(&data);
+        assert_eq!(
+            steps(&report),
+            vec![
+                (FrameStep::Sof, true),
+                (FrameStep::HeaderCrc8, true),
+                (FrameStep::Length, true),
+                (FrameStep::DataCrc32, false),
+            ]
+        );
+        assert!(report.packet.is_none());
+        assert_eq!(report.unconsumed, data[1..]);
+
+        let report = WhoopPacket::check_frame(&[0xaa, 0x08, 0x00]);
+        assert_eq!(
+            steps(&report),
+            vec![(FrameStep::Sof, true), (FrameStep::HeaderCrc8, false)]
+        );
+
+        let report = WhoopPacket::check_frame(&[0x00]);
+        assert_eq!(steps(&report), vec![(FrameStep::Sof, false)]);
+    }
+}
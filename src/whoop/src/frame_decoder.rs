@@ -0,0 +1,200 @@
+use crate::WhoopPacket;
+
+/// Reassembles [`WhoopPacket`]s from a stream of BLE notifications.
+///
+/// Whoop doesn't guarantee that one notification carries exactly one frame, a frame can be split
+/// across several notifications and several frames can arrive in a single one, so bytes are
+/// buffered until a whole frame (SOF, length, header CRC8, payload, CRC32) is available.
+/// Garbage in front of a frame is skipped by looking for the next SOF with a valid header CRC8
+/// and a declared length no frame could exceed.
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    skipped: usize,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `chunk` to the internal buffer and returns every complete packet found so far
+    pub fn decode(&mut self, chunk: &[u8]) -> Vec<WhoopPacket> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut packets = Vec::new();
+        while let Some(packet) = self.next_packet() {
+            packets.push(packet);
+        }
+
+        packets
+    }
+
+    /// Number of bytes dropped while resynchronising
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Number of bytes waiting for the rest of their frame
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.skipped = 0;
+    }
+
+    fn next_packet(&mut self) -> Option<WhoopPacket> {
+        loop {
+            self.sync_to_header()?;
+
+            let length = u16::from_le_bytes([self.buffer[1], self.buffer[2]]) as usize;
+            let frame_len = WhoopPacket::HEADER_LEN + length;
+            if self.buffer.len() < frame_len {
+                return None;
+            }
+
+            match WhoopPacket::from_data(&self.buffer[..frame_len]) {
+                Ok(packet) => {
+                    self.buffer.drain(..frame_len);
+                    return Some(packet);
+                }
+                Err(_) => self.skip(1),
+            }
+        }
+    }
+
+    /// Drops bytes until buffer starts with SOF followed by a valid header,
+    /// returns `None` if there are not enough bytes to check the header yet
+    fn sync_to_header(&mut self) -> Option<()> {
+        loop {
+            match self.buffer.iter().position(|&b| b == WhoopPacket::SOF) {
+                Some(start) => self.skip(start),
+                None => {
+                    self.skip(self.buffer.len());
+                    return None;
+                }
+            }
+
+            if self.buffer.len() < WhoopPacket::HEADER_LEN {
+                return None;
+            }
+
+            let length = usize::from(u16::from_le_bytes([self.buffer[1], self.buffer[2]]));
+            let valid_crc = WhoopPacket::crc8(&self.buffer[1..3]) == self.buffer[3];
+            let valid_length =
+                (WhoopPacket::MIN_LENGTH..=WhoopPacket::MAX_LENGTH).contains(&length);
+            if valid_crc && valid_length {
+                return Some(());
+            }
+
+            self.skip(1);
+        }
+    }
+
+    fn skip(&mut self, n: usize) {
+        self.buffer.drain(..n);
+        self.skipped += n;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::PacketType;
+
+    use super::*;
+
+    fn frames() -> Vec<WhoopPacket> {
+        vec![
+            WhoopPacket::new(PacketType::Command, 1, 5, vec![0x01, 0x02, 0x03]),
+            WhoopPacket::new(PacketType::HistoricalData, 2, 6, vec![0xAA; 40]),
+            WhoopPacket::new(PacketType::Metadata, 3, 2, vec![0x00; 16]),
+        ]
+    }
+
+    fn assert_same(decoded: &[WhoopPacket], expected: &[WhoopPacket]) {
+        assert_eq!(decoded.len(), expected.len());
+        for (decoded, expected) in decoded.iter().zip(expected) {
+            assert_eq!(decoded.framed_packet(), expected.framed_packet());
+        }
+    }
+
+    #[test]
+    fn decode_fragmented_frames() {
+        let expected = frames();
+        let stream = expected
+            .iter()
+            .flat_map(WhoopPacket::framed_packet)
+            .collect::<Vec<_>>();
+
+        for chunk_size in [1, 3, 7, 20, stream.len()] {
+            let mut decoder = FrameDecoder::new();
+            let decoded = stream
+                .chunks(chunk_size)
+                .flat_map(|chunk| decoder.decode(chunk))
+                .collect::<Vec<_>>();
+
+            assert_same(&decoded, &expected);
+            assert_eq!(decoder.pending(), 0);
+            assert_eq!(decoder.skipped(), 0);
+        }
+    }
+
+    #[test]
+    fn resync_after_garbage() {
+        let expected = frames();
+        let mut stream = vec![0x00, 0xAA, 0x13, 0xAA, 0x0C, 0x00];
+        for packet in &expected {
+            stream.extend(packet.framed_packet());
+        }
+
+        // Frame with a valid header but broken CRC32 right after the garbage
+        let mut corrupted = expected[0].framed_packet();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xFF;
+        stream.splice(6..6, corrupted);
+
+        let mut decoder = FrameDecoder::new();
+        let decoded = decoder.decode(&stream);
+
+        assert_same(&decoded, &expected);
+        assert_eq!(decoder.pending(), 0);
+        assert!(decoder.skipped() > 0);
+    }
+
+    #[test]
+    fn resync_after_oversized_length() {
+        let expected = frames();
+        // Header with valid CRC8 declaring more bytes than any frame has
+        let length = u16::MAX.to_le_bytes();
+        let mut stream = vec![WhoopPacket::SOF, length[0], length[1]];
+        stream.push(WhoopPacket::crc8(&length));
+        for packet in &expected {
+            stream.extend(packet.framed_packet());
+        }
+
+        let mut decoder = FrameDecoder::new();
+        let decoded = decoder.decode(&stream);
+
+        assert_same(&decoded, &expected);
+        assert_eq!(decoder.pending(), 0);
+        assert_eq!(decoder.skipped(), 4);
+
+        decoder.reset();
+        assert_eq!(decoder.skipped(), 0);
+    }
+
+    #[test]
+    fn wait_for_rest_of_frame() {
+        let framed = frames().remove(1).framed_packet();
+        let mut decoder = FrameDecoder::new();
+
+        assert!(decoder.decode(&framed[..10]).is_empty());
+        assert_eq!(decoder.pending(), 10);
+
+        let decoded = decoder.decode(&framed[10..]);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].data, vec![0xAA; 40]);
+    }
+}
@@ -6,7 +6,6 @@ pub struct InvalidIndexError;
 
 pub trait BufferReader {
     fn read<const N: usize>(&mut self) -> Result<[u8; N]>;
-    fn read_end<const N: usize>(&mut self) -> Result<[u8; N]>;
     fn pop_front(&mut self) -> Result<u8>;
 
     fn read_u32_le(&mut self) -> Result<u32> {
@@ -15,38 +14,27 @@ pub trait BufferReader {
     fn read_u16_le(&mut self) -> Result<u16> {
         Ok(u16::from_le_bytes(self.read()?))
     }
+    fn read_i16_le(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.read()?))
+    }
+    fn read_f32_le(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.read()?))
+    }
 }
 
-impl BufferReader for Vec<u8> {
+/// Reading from a slice moves it forward, bytes are never copied out of the underlying buffer
+/// except for the returned arrays
+impl BufferReader for &[u8] {
     fn read<const N: usize>(&mut self) -> Result<[u8; N]> {
-        if self.len() < N {
-            return Err(InvalidIndexError);
-        }
-
-        self.drain(0..N)
-            .collect::<Vec<u8>>()
-            .try_into()
-            .map_err(|_| InvalidIndexError)
-    }
-
-    fn read_end<const N: usize>(&mut self) -> Result<[u8; N]> {
-        let size = self.len();
-        if size < N {
-            return Err(InvalidIndexError);
-        }
-
-        self.drain((size - N)..size)
-            .collect::<Vec<u8>>()
-            .try_into()
-            .map_err(|_| InvalidIndexError)
+        let (head, rest) = self.split_first_chunk::<N>().ok_or(InvalidIndexError)?;
+        *self = rest;
+        Ok(*head)
     }
 
     fn pop_front(&mut self) -> Result<u8> {
-        if !self.is_empty() {
-            Ok(self.remove(0))
-        } else {
-            Err(InvalidIndexError)
-        }
+        let (first, rest) = self.split_first().ok_or(InvalidIndexError)?;
+        *self = rest;
+        Ok(*first)
     }
 }
 
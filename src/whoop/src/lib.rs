@@ -1,6 +1,12 @@
 mod packet;
 pub use packet::WhoopPacket;
 
+mod frame_decoder;
+pub use frame_decoder::FrameDecoder;
+
+mod frame_check;
+pub use frame_check::{FrameCheck, FrameReport, FrameStep};
+
 mod error;
 pub use error::WhoopError;
 
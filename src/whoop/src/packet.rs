@@ -1,8 +1,8 @@
 use std::fmt;
 
-use crate::{constants::PacketType, error::WhoopError, helpers::BufferReader};
+use crate::{constants::PacketType, error::WhoopError, FrameStep};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct WhoopPacket {
     pub packet_type: PacketType,
     pub seq: u8,
@@ -11,7 +11,15 @@ pub struct WhoopPacket {
 }
 
 impl WhoopPacket {
-    const SOF: u8 = 0xAA;
+    pub(crate) const SOF: u8 = 0xAA;
+    /// Frame layout: `[SOF][length: u16][crc8 of length][type][seq][cmd][data..][crc32]`,
+    /// where length counts bytes after header, including CRC32
+    pub(crate) const HEADER_LEN: usize = 4;
+    /// Length of frame without data: type, seq, cmd and CRC32
+    pub(crate) const MIN_LENGTH: usize = 7;
+    /// Upper bound of declared length, far above largest frames strap sends (IMU and optical
+    /// batches), so garbage header with valid CRC8 isn't waited for
+    pub(crate) const MAX_LENGTH: usize = 4096;
 
     pub fn with_seq(self, seq: u8) -> WhoopPacket {
         WhoopPacket { seq, ..self }
@@ -26,47 +34,27 @@ impl WhoopPacket {
         }
     }
 
-    pub fn from_data(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+    /// Validates and parses `data` holding exactly one frame, see [`WhoopPacket::check_frame`]
+    /// for steps it is validated with
+    pub fn from_data(data: &[u8]) -> Result<Self, WhoopError> {
         if data.len() < 8 {
             return Err(WhoopError::PacketTooShort);
         }
 
-        let sof = data.pop_front()?;
-        if sof != Self::SOF {
-            return Err(WhoopError::InvalidSof);
-        }
-
-        // Verify header CRC8
-        let length_buffer = data.read::<2>()?;
-        let expected_crc8 = data.pop_front()?;
-        let calculated_crc8 = Self::crc8(&length_buffer);
-
-        if calculated_crc8 != expected_crc8 {
-            return Err(WhoopError::InvalidHeaderCrc8);
-        }
+        let (packet, length) =
+            Self::validate_frame(data, |_, _, _| {}).map_err(|step| match step {
+                FrameStep::Sof => WhoopError::InvalidSof,
+                FrameStep::HeaderCrc8 => WhoopError::InvalidHeaderCrc8,
+                FrameStep::Length => WhoopError::InvalidPacketLength,
+                FrameStep::DataCrc32 => WhoopError::InvalidDataCrc32,
+                FrameStep::PacketType => WhoopError::InvalidPacketType(data[Self::HEADER_LEN]),
+            })?;
 
-        // Verify data CRC32
-        let length = u16::from_le_bytes(length_buffer) as usize;
-        if length > data.len() || length < 8 {
+        if length != data.len() {
             return Err(WhoopError::InvalidPacketLength);
         }
 
-        let expected_crc32 = u32::from_le_bytes(data.read_end()?);
-        let calculated_crc32 = Self::crc32(&data);
-        if calculated_crc32 != expected_crc32 {
-            return Err(WhoopError::InvalidDataCrc32);
-        }
-
-        Ok(Self {
-            packet_type: {
-                let packet_type = data.pop_front()?;
-                PacketType::from_u8(packet_type)
-                    .ok_or(WhoopError::InvalidPacketType(packet_type))?
-            },
-            seq: data.pop_front()?,
-            cmd: data.pop_front()?,
-            data,
-        })
+        Ok(packet)
     }
 
     fn create_packet(&self) -> Vec<u8> {
@@ -78,7 +66,7 @@ impl WhoopPacket {
         packet
     }
 
-    fn crc8(data: &[u8]) -> u8 {
+    pub(crate) fn crc8(data: &[u8]) -> u8 {
         let mut crc: u8 = 0;
         for &byte in data {
             crc ^= byte;
@@ -93,7 +81,7 @@ impl WhoopPacket {
         crc
     }
 
-    fn crc32(data: &[u8]) -> u32 {
+    pub(crate) fn crc32(data: &[u8]) -> u32 {
         let mut crc: u32 = 0xFFFFFFFF;
         for &byte in data {
             crc ^= u32::from(byte);
@@ -158,11 +146,73 @@ mod tests {
     fn test_packet_parsing() {
         let original_packet = WhoopPacket::new(PacketType::Command, 1, 5, vec![0x01, 0x02, 0x03]);
         let framed = original_packet.framed_packet();
-        let parsed = WhoopPacket::from_data(framed).unwrap();
+        let parsed = WhoopPacket::from_data(&framed).unwrap();
 
         assert_eq!(parsed.packet_type, original_packet.packet_type);
         assert_eq!(parsed.seq, original_packet.seq);
         assert_eq!(parsed.cmd, original_packet.cmd);
         assert_eq!(parsed.data, original_packet.data);
     }
+
+    #[test]
+    fn empty_payload() {
+        let packet = WhoopPacket::new(PacketType::Command, 1, 5, vec![]);
+        let framed = packet.framed_packet();
+        assert_eq!(framed.len(), 4 + WhoopPacket::MIN_LENGTH);
+
+        let parsed = WhoopPacket::from_data(&framed).expect("Valid frame");
+        assert_eq!(parsed.cmd, 5);
+        assert!(parsed.data.is_empty());
+
+        // Declared length can't fit type, seq, cmd and CRC32
+        let mut short = framed.clone();
+        short[1] = (WhoopPacket::MIN_LENGTH - 1) as u8;
+        short[3] = WhoopPacket::crc8(&short[1..3]);
+        assert!(matches!(
+            WhoopPacket::from_data(&short),
+            Err(WhoopError::InvalidPacketLength)
+        ));
+    }
+
+    #[test]
+    fn errors_match_failed_check() {
+        let framed = WhoopPacket::get_clock().framed_packet();
+        let corrupt = |index: usize| {
+            let mut data = framed.clone();
+            data[index] ^= 0xff;
+            data
+        };
+
+        let failed_step = |data: &[u8]| WhoopPacket::check_frame(data).checks.pop().map(|c| c.step);
+
+        let data = corrupt(0);
+        assert_eq!(failed_step(&data), Some(FrameStep::Sof));
+        assert!(matches!(
+            WhoopPacket::from_data(&data),
+            Err(WhoopError::InvalidSof)
+        ));
+
+        let data = corrupt(3);
+        assert_eq!(failed_step(&data), Some(FrameStep::HeaderCrc8));
+        assert!(matches!(
+            WhoopPacket::from_data(&data),
+            Err(WhoopError::InvalidHeaderCrc8)
+        ));
+
+        let data = corrupt(framed.len() - 1);
+        assert_eq!(failed_step(&data), Some(FrameStep::DataCrc32));
+        assert!(matches!(
+            WhoopPacket::from_data(&data),
+            Err(WhoopError::InvalidDataCrc32)
+        ));
+
+        // Frame followed by other bytes is valid for `check_frame` only
+        let mut trailing = framed.clone();
+        trailing.push(0x00);
+        assert!(WhoopPacket::check_frame(&trailing).passed());
+        assert!(matches!(
+            WhoopPacket::from_data(&trailing),
+            Err(WhoopError::InvalidPacketLength)
+        ));
+    }
 }
@@ -24,6 +24,69 @@ impl WhoopPacket {
         )
     }
 
+    pub fn toggle_realtime_hr(enable: bool) -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::ToggleRealtimeHr.as_u8(),
+            vec![enable as u8],
+        )
+    }
+
+    pub fn toggle_imu_mode(enable: bool) -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::ToggleImuMode.as_u8(),
+            vec![enable as u8],
+        )
+    }
+
+    pub fn toggle_imu_mode_historical(enable: bool) -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::ToggleImuModeHistorical.as_u8(),
+            vec![enable as u8],
+        )
+    }
+
+    pub fn start_raw_data() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::StartRawData.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    pub fn stop_raw_data() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::StopRawData.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    pub fn enable_optical_data(enable: bool) -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::EnableOpticalData.as_u8(),
+            vec![enable as u8],
+        )
+    }
+
+    pub fn toggle_optical_mode(enable: bool) -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::ToggleOpticalMode.as_u8(),
+            vec![enable as u8],
+        )
+    }
+
     pub fn history_start() -> WhoopPacket {
         WhoopPacket::new(
             PacketType::Command,
@@ -51,6 +114,33 @@ impl WhoopPacket {
         )
     }
 
+    pub fn get_clock() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::GetClock.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    pub fn get_battery_level() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::GetBatteryLevel.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    pub fn version_info() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::ReportVersionInfo.as_u8(),
+            vec![0x00],
+        )
+    }
+
     pub fn set_time() -> WhoopPacket {
         let mut data = vec![];
         let current_time = Utc::now().timestamp() as u32;
@@ -76,4 +166,41 @@ impl WhoopPacket {
             packet_data,
         )
     }
+
+    /// Stops sending history, last `HistoryEnd` stays unacknowledged so its records aren't trimmed
+    pub fn abort_historical_transmits() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::AbortHistoricalTransmits.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    /// Asks strap for range of stored history, answered with [`CommandResponse::DataRange`](crate::CommandResponse::DataRange).
+    /// Layout is guessed and not verified against captured command yet
+    pub fn get_data_range() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::GetDataRange.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    /// Moves position history is sent from, `pointer` is data of `HistoryEnd` metadata.
+    /// Layout is guessed from [`WhoopPacket::history_end`] and not verified against captured
+    /// command yet
+    pub fn set_read_pointer(pointer: u32) -> WhoopPacket {
+        let mut packet_data = vec![0x01];
+        packet_data.extend_from_slice(&pointer.to_le_bytes());
+        packet_data.append(&mut vec![0, 0, 0, 0]); // padding
+
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::SetReadPointer.as_u8(),
+            packet_data,
+        )
+    }
 }
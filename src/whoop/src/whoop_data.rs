@@ -5,11 +5,34 @@ use crate::{
 };
 
 mod history;
-pub use history::{Activity, HistoryReading, ParsedHistoryReading};
+pub use history::{
+    Activity, HistoryReading, HistoryReadingV2, HistoryRecordExtension, ParsedHistoryReading,
+    RealtimeHeartRate,
+};
+
+mod history_decoders;
+pub use history_decoders::{HistoryDecoder, HistoryDecoders};
+
+mod command_response;
+pub use command_response::CommandResponse;
+
+mod strap_event;
+pub use strap_event::StrapEvent;
+
+mod imu;
+pub use imu::{ImuData, ImuSample};
+
+mod raw_optical;
+pub use raw_optical::{ParsedPpgSample, PpgSample, RawOpticalData};
 
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
 pub enum WhoopData {
     HistoryReading(HistoryReading),
+    RealtimeHeartRate(RealtimeHeartRate),
+    RealtimeImu(ImuData),
+    HistoricalImu(ImuData),
+    RawOptical(RawOpticalData),
     HistoryMetadata {
         unix: u32,
         data: u32,
@@ -30,24 +53,50 @@ pub enum WhoopData {
         unix: u32,
         event: u8,
     },
+    CommandResponse(CommandResponse),
 }
 
 impl WhoopData {
+    /// Historical records are parsed with [`HistoryDecoders::known`]
     pub fn from_packet(packet: WhoopPacket) -> Result<Self, WhoopError> {
+        Self::from_packet_with(packet, HistoryDecoders::known())
+    }
+
+    /// Same as [`WhoopData::from_packet`], historical records are parsed with `history` decoders
+    pub fn from_packet_with(
+        packet: WhoopPacket,
+        history: &HistoryDecoders,
+    ) -> Result<Self, WhoopError> {
         match packet.packet_type {
-            PacketType::HistoricalData => Self::parse_historical_packet(packet.data),
-            PacketType::Metadata => Self::parse_metadata(packet),
-            PacketType::ConsoleLogs => Self::parse_console_log(packet.data),
-            PacketType::Event => Self::parse_event(packet),
+            PacketType::HistoricalData => history
+                .decode(&packet)
+                .map(|record| Self::HistoryReading(record.into())),
+            PacketType::RealtimeData => Self::parse_realtime_hr(&packet.data),
+            PacketType::RealtimeImuDataStream => {
+                Ok(Self::RealtimeImu(ImuData::parse(&packet.data)?))
+            }
+            PacketType::RealtimeRawData => {
+                Ok(Self::RawOptical(RawOpticalData::parse(&packet.data)?))
+            }
+            PacketType::HistoricalImuDataStream => {
+                Ok(Self::HistoricalImu(ImuData::parse(&packet.data)?))
+            }
+            PacketType::Metadata => Self::parse_metadata(&packet),
+            PacketType::ConsoleLogs => Self::parse_console_log(&packet.data),
+            PacketType::Event => Self::parse_event(&packet),
+            PacketType::CommandResponse => {
+                Ok(Self::CommandResponse(CommandResponse::from_packet(&packet)))
+            }
             _ => Err(WhoopError::Unimplemented),
         }
     }
 
-    fn parse_event(mut packet: WhoopPacket) -> Result<Self, WhoopError> {
+    fn parse_event(packet: &WhoopPacket) -> Result<Self, WhoopError> {
         let command = CommandNumber::from_u8(packet.cmd).ok_or(packet.cmd);
 
-        let _ = packet.data.pop_front()?;
-        let unix = packet.data.read_u32_le()?;
+        let mut data = packet.data.as_slice();
+        let _ = data.pop_front()?;
+        let unix = data.read_u32_le()?;
 
         match command {
             Ok(CommandNumber::RunAlarm) => Ok(Self::RunAlarm { unix }),
@@ -68,7 +117,7 @@ impl WhoopData {
         }
     }
 
-    fn parse_console_log(mut packet: Vec<u8>) -> Result<Self, WhoopError> {
+    fn parse_console_log(mut packet: &[u8]) -> Result<Self, WhoopError> {
         let _ = packet.pop_front()?;
         let unix = packet.read_u32_le()?;
 
@@ -98,58 +147,48 @@ impl WhoopData {
         Ok(Self::ConsoleLog { unix, log })
     }
 
-    fn parse_metadata(mut packet: WhoopPacket) -> Result<Self, WhoopError> {
+    fn parse_metadata(packet: &WhoopPacket) -> Result<Self, WhoopError> {
         let cmd =
             MetadataType::from_u8(packet.cmd).ok_or(WhoopError::InvalidMetadataType(packet.cmd))?;
 
-        let unix = packet.data.read_u32_le()?;
-        let _padding = packet.data.read::<6>()?;
-        let data = packet.data.read_u32_le()?;
+        let mut reader = packet.data.as_slice();
+        let unix = reader.read_u32_le()?;
+        let _padding = reader.read::<6>()?;
+        let data = reader.read_u32_le()?;
 
         Ok(Self::HistoryMetadata { unix, data, cmd })
     }
 
-    fn parse_historical_packet(mut packet: Vec<u8>) -> Result<Self, WhoopError> {
-        let _something = packet.read::<4>();
+    fn parse_realtime_hr(mut packet: &[u8]) -> Result<Self, WhoopError> {
         let unix = packet.read_u32_le()?;
-        let _something = packet.read::<6>();
+        let _something = packet.read::<2>()?;
         let bpm = packet.pop_front()?;
         let rr_count = packet.pop_front()?;
-        let mut rr = Vec::new();
-        for _ in 0..4 {
-            let rr_ = packet.read_u16_le()?;
-            if rr_ == 0 {
-                continue;
-            }
-            rr.push(rr_);
-        }
-        if rr.len() as u8 != rr_count {
-            return Err(WhoopError::InvalidData);
+        let mut rr = Vec::with_capacity(rr_count.into());
+        for _ in 0..rr_count {
+            rr.push(packet.read_u16_le()?);
         }
 
-        let activity = packet.read_u32_le()?;
-
-        Ok(Self::HistoryReading(HistoryReading {
-            unix,
-            bpm,
-            rr,
-            activity,
-        }))
+        Ok(Self::RealtimeHeartRate(RealtimeHeartRate { unix, bpm, rr }))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        constants::{MetadataType, PacketType},
-        whoop_data::{history::HistoryReading, WhoopData},
-        WhoopPacket,
+        constants::{CommandNumber, MetadataType, PacketType},
+        whoop_data::{
+            history::{HistoryReading, HistoryReadingV2, RealtimeHeartRate},
+            CommandResponse, HistoryDecoders, ImuData, ImuSample, PpgSample, RawOpticalData,
+            WhoopData,
+        },
+        WhoopError, WhoopPacket,
     };
 
     #[test]
     fn parse_historical_packet() {
         let data = hex::decode("aa5c00f02f0c053f940900da106966280080545401360195040000000000000000a34cff0050bf3b144efb3da4a4463f299c0dbf00004c42144efb3da4a4463f299c0dbff40155023b03530255016004010c020c2000000000000002e8c17c8d").expect("Invalid hex data");
-        let packet = WhoopPacket::from_data(data).expect("Invalid packet data");
+        let packet = WhoopPacket::from_data(&data).expect("Invalid packet data");
         let data = WhoopData::from_packet(packet).expect("Invalid packet");
 
         assert_eq!(
@@ -158,12 +197,13 @@ mod tests {
                 unix: 1718161626,
                 bpm: 54,
                 rr: vec![1173],
-                activity: 1285750784
+                activity: 1285750784,
+                skin_temp_raw: None,
             })
         );
 
         let data = hex::decode("aa6400a12f1805cb6cc100f7715c67300b805454015700000000000000000000005161cda013a03dcdcc1cbbd723133ee146873f00028a46cdcc1cbbd723133ee146873f28026d029c03700257019004010c020c3000000000000001b9120000000000000a9c4cac").expect("Invalid hex data");
-        let packet = WhoopPacket::from_data(data).expect("Invalid packet data");
+        let packet = WhoopPacket::from_data(&data).expect("Invalid packet data");
         let data = WhoopData::from_packet(packet).expect("Invalid packet");
 
         assert_eq!(
@@ -172,14 +212,15 @@ mod tests {
                 unix: 1734111735,
                 bpm: 87,
                 rr: Vec::new(),
-                activity: 1632698368
+                activity: 1632698368,
+                skin_temp_raw: Some(4793),
             })
         );
 
         let data = hex::decode("aa1c00ab31370268ae7667702d32000000c7b6000010000000000000e01eba47")
             .expect("Invalid hex data");
 
-        let packet = WhoopPacket::from_data(data).expect("Invalid packet data");
+        let packet = WhoopPacket::from_data(&data).expect("Invalid packet data");
         let data = WhoopData::from_packet(packet).expect("Invalid packet");
 
         assert_eq!(
@@ -192,6 +233,150 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_historical_record_v2() {
+        let data = hex::decode("aa5c00f02f0c053f940900da106966280080545401360195040000000000000000a34cff0050bf3b144efb3da4a4463f299c0dbf00004c42144efb3da4a4463f299c0dbff40155023b03530255016004010c020c2000000000000002e8c17c8d").expect("Invalid hex data");
+        let packet = WhoopPacket::from_data(&data).expect("Invalid packet data");
+        let record = HistoryReadingV2::from_packet(&packet).expect("Invalid record");
+
+        assert_eq!(record.sequence, 627775);
+        assert_eq!(record.unix, 1718161626);
+        assert_eq!(record.unknown_header, [0x28, 0x00, 0x80, 0x54, 0x54, 0x01]);
+        assert_eq!(record.bpm, 54);
+        assert_eq!(record.rr, vec![1173]);
+
+        let extension = record.extension.clone().expect("Missing extension");
+        assert_eq!(extension.unknown_flags, 0xff);
+        assert_eq!(extension.unknown_scalar_b, 51.0);
+        assert_eq!(extension.gravity, extension.gravity_repeat);
+        let magnitude = extension.gravity.iter().map(|g| g * g).sum::<f32>().sqrt();
+        assert!((magnitude - 1.0).abs() < 0.05);
+        assert_eq!(extension.series, [500, 597, 827, 595, 341, 1120]);
+        assert_eq!(extension.tail.len(), 12);
+
+        let WhoopData::HistoryReading(reading) =
+            WhoopData::from_packet(packet).expect("Invalid packet")
+        else {
+            panic!("Expected history reading");
+        };
+        assert_eq!(HistoryReading::from(record), reading);
+
+        // Records that end after `activity` still parse
+        let short = HistoryReadingV2::parse(&data[7..35]).expect("Invalid record");
+        assert_eq!(short.activity, 1285750784);
+        assert_eq!(short.extension, None);
+    }
+
+    #[test]
+    fn history_decoders() {
+        let data = hex::decode("aa5c00f02f0c053f940900da106966280080545401360195040000000000000000a34cff0050bf3b144efb3da4a4463f299c0dbf00004c42144efb3da4a4463f299c0dbff40155023b03530255016004010c020c2000000000000002e8c17c8d").expect("Invalid hex data");
+        let packet = WhoopPacket::from_data(&data).expect("Invalid packet data");
+        assert_eq!(packet.cmd, 5);
+
+        let decoders = HistoryDecoders::default();
+        assert_eq!(decoders.versions().collect::<Vec<_>>(), vec![5]);
+        assert!(std::ptr::eq(
+            HistoryDecoders::known(),
+            HistoryDecoders::known()
+        ));
+
+        let unknown = WhoopPacket::new(PacketType::HistoricalData, 0, 42, packet.data.clone());
+        assert!(matches!(
+            decoders.decode(&unknown),
+            Err(WhoopError::UnknownHistoryVersion(42))
+        ));
+
+        let decoders = decoders.register(42, |data| {
+            let mut record = HistoryReadingV2::parse(data)?;
+            record.extension = None;
+            Ok(record)
+        });
+        let data = WhoopData::from_packet_with(unknown, &decoders).expect("Invalid packet");
+        let WhoopData::HistoryReading(reading) = data else {
+            panic!("Expected history reading");
+        };
+        assert_eq!(reading.bpm, 54);
+    }
+
+    #[test]
+    fn parse_realtime_hr() {
+        let packet = WhoopPacket::toggle_realtime_hr(true);
+        assert_eq!(packet.cmd, CommandNumber::ToggleRealtimeHr.as_u8());
+        assert_eq!(packet.data, vec![0x01]);
+
+        let packet = WhoopPacket::new(
+            PacketType::RealtimeData,
+            0,
+            0,
+            hex::decode("68ae766700004802e803ba03").expect("Invalid hex data"),
+        );
+        let data = WhoopData::from_packet(packet).expect("Invalid data");
+
+        assert_eq!(
+            data,
+            WhoopData::RealtimeHeartRate(RealtimeHeartRate {
+                unix: 1735831144,
+                bpm: 72,
+                rr: vec![1000, 954],
+            })
+        );
+    }
+
+    #[test]
+    fn parse_imu() {
+        let packet = WhoopPacket::new(
+            PacketType::HistoricalImuDataStream,
+            0,
+            0,
+            hex::decode("68ae7667000002000100feff00f00a00f6ff00000200fdff0000e80305000000")
+                .expect("Invalid hex data"),
+        );
+        let data = WhoopData::from_packet(packet).expect("Invalid data");
+
+        assert_eq!(
+            data,
+            WhoopData::HistoricalImu(ImuData {
+                unix: 1735831144,
+                samples: vec![
+                    ImuSample {
+                        accelerometer: [1, -2, -4096],
+                        gyroscope: [10, -10, 0],
+                    },
+                    ImuSample {
+                        accelerometer: [2, -3, 0],
+                        gyroscope: [1000, 5, 0],
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_raw_optical() {
+        let packet = WhoopPacket::new(
+            PacketType::RealtimeRawData,
+            0,
+            0,
+            hex::decode("68ae766719000100a0860100400d0300801a0600e8030000")
+                .expect("Invalid hex data"),
+        );
+        let data = WhoopData::from_packet(packet).expect("Invalid data");
+
+        assert_eq!(
+            data,
+            WhoopData::RawOptical(RawOpticalData {
+                unix: 1735831144,
+                sample_rate: 25,
+                samples: vec![PpgSample {
+                    green: 100_000,
+                    red: 200_000,
+                    infrared: 400_000,
+                    ambient: 1000,
+                }]
+            })
+        );
+    }
+
     #[test]
     fn parse_console_logs() {
         let packet = WhoopPacket{
@@ -231,7 +416,7 @@ mod tests {
     fn parse_metadata() {
         let bytes = hex::decode("aa1c00ab311002a9fc8367205337000000257e00000a0000000000007ac020f8")
             .expect("invalid bytes");
-        let packet = WhoopPacket::from_data(bytes).expect("Invalid packet");
+        let packet = WhoopPacket::from_data(&bytes).expect("Invalid packet");
         let data = WhoopData::from_packet(packet).expect("invalid packet");
         assert_eq!(
             data,
@@ -243,7 +428,7 @@ mod tests {
         );
 
         let bytes = hex::decode("aa2c005231010146fb8367404c0600000010000000020000002900000010000000030000000000000008020055fd251d").expect("invalid bytes");
-        let packet = WhoopPacket::from_data(bytes).expect("Invalid packet");
+        let packet = WhoopPacket::from_data(&bytes).expect("Invalid packet");
         let data = WhoopData::from_packet(packet).expect("invalid packet");
         assert_eq!(
             data,
@@ -254,4 +439,65 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn parse_command_response() {
+        let response = |cmd: CommandNumber, data: &str| {
+            let packet = WhoopPacket::new(
+                PacketType::CommandResponse,
+                0,
+                cmd.as_u8(),
+                hex::decode(data).expect("Invalid hex data"),
+            );
+            WhoopData::from_packet(packet).expect("Invalid data")
+        };
+
+        assert_eq!(
+            response(CommandNumber::GetBatteryLevel, "01005203"),
+            WhoopData::CommandResponse(CommandResponse::BatteryLevel { level: 850 })
+        );
+
+        assert_eq!(
+            response(CommandNumber::GetClock, "010068ae7667"),
+            WhoopData::CommandResponse(CommandResponse::Clock { unix: 1735831144 })
+        );
+
+        assert_eq!(
+            response(CommandNumber::EnterHighFreqSync, "0100"),
+            WhoopData::CommandResponse(CommandResponse::Ack {
+                cmd: CommandNumber::EnterHighFreqSync
+            })
+        );
+
+        assert_eq!(
+            response(CommandNumber::ToggleRealtimeHr, "0100"),
+            WhoopData::CommandResponse(CommandResponse::Ack {
+                cmd: CommandNumber::ToggleRealtimeHr
+            })
+        );
+
+        assert_eq!(
+            response(
+                CommandNumber::GetAdvertisingNameHarvard,
+                "010006574830303031"
+            ),
+            WhoopData::CommandResponse(CommandResponse::AdvertisingName {
+                name: "WH0001".to_owned()
+            })
+        );
+
+        assert_eq!(
+            response(CommandNumber::GetDataRange, "01001400000032000000"),
+            WhoopData::CommandResponse(CommandResponse::DataRange { start: 20, end: 50 })
+        );
+
+        // too short for a battery level, kept raw
+        assert_eq!(
+            response(CommandNumber::GetBatteryLevel, "0100"),
+            WhoopData::CommandResponse(CommandResponse::Raw {
+                cmd: CommandNumber::GetBatteryLevel.as_u8(),
+                data: vec![0x01, 0x00]
+            })
+        );
+    }
 }
@@ -0,0 +1,145 @@
+use crate::{constants::CommandNumber, helpers::BufferReader, WhoopError, WhoopPacket};
+
+/// Reply to a command sent on `CMD_TO_STRAP`, keyed by the command it answers.
+///
+/// First two bytes of every response are a header, payload starts after it.
+/// Responses that can't be parsed (unknown command or unexpected layout) are kept as [`CommandResponse::Raw`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+pub enum CommandResponse {
+    /// Command was accepted, used for commands that don't return anything
+    Ack {
+        cmd: CommandNumber,
+    },
+    Clock {
+        unix: u32,
+    },
+    /// Battery level in tenths of percent
+    BatteryLevel {
+        level: u16,
+    },
+    HelloHarvard {
+        charging: bool,
+        is_worn: bool,
+    },
+    AdvertisingName {
+        name: String,
+    },
+    VersionInfo {
+        harvard: String,
+        boylston: String,
+    },
+    /// Stored history, `start` is pointer to oldest record that wasn't trimmed and `end` is
+    /// pointer after newest one, pointers are same values as data of `HistoryEnd` metadata.
+    ///
+    /// Layout is guessed and not verified against captured response yet
+    DataRange {
+        start: u32,
+        end: u32,
+    },
+    Raw {
+        cmd: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl CommandResponse {
+    const CHARGING_OFFSET: usize = 7;
+    const IS_WORN_OFFSET: usize = 116;
+
+    pub fn from_packet(packet: &WhoopPacket) -> Self {
+        Self::parse(packet).unwrap_or_else(|_| Self::Raw {
+            cmd: packet.cmd,
+            data: packet.data.clone(),
+        })
+    }
+
+    /// Command this response answers, `None` if command number is unknown
+    pub fn command(&self) -> Option<CommandNumber> {
+        match self {
+            Self::Ack { cmd } => Some(*cmd),
+            Self::Clock { .. } => Some(CommandNumber::GetClock),
+            Self::BatteryLevel { .. } => Some(CommandNumber::GetBatteryLevel),
+            Self::HelloHarvard { .. } => Some(CommandNumber::GetHelloHarvard),
+            Self::AdvertisingName { .. } => Some(CommandNumber::GetAdvertisingNameHarvard),
+            Self::VersionInfo { .. } => Some(CommandNumber::ReportVersionInfo),
+            Self::DataRange { .. } => Some(CommandNumber::GetDataRange),
+            Self::Raw { cmd, .. } => CommandNumber::from_u8(*cmd),
+        }
+    }
+
+    fn parse(packet: &WhoopPacket) -> Result<Self, WhoopError> {
+        let cmd =
+            CommandNumber::from_u8(packet.cmd).ok_or(WhoopError::InvalidCommandType(packet.cmd))?;
+
+        let mut data = packet.data.as_slice();
+        let _header = data.read::<2>()?;
+
+        match cmd {
+            CommandNumber::EnterHighFreqSync
+            | CommandNumber::ExitHighFreqSync
+            | CommandNumber::SendHistoricalData
+            | CommandNumber::HistoricalDataResult
+            | CommandNumber::AbortHistoricalTransmits
+            | CommandNumber::SetReadPointer
+            | CommandNumber::SetClock
+            | CommandNumber::ToggleRealtimeHr
+            | CommandNumber::ToggleImuMode
+            | CommandNumber::ToggleImuModeHistorical
+            | CommandNumber::StartRawData
+            | CommandNumber::StopRawData
+            | CommandNumber::EnableOpticalData
+            | CommandNumber::ToggleOpticalMode => Ok(Self::Ack { cmd }),
+            CommandNumber::GetClock => Ok(Self::Clock {
+                unix: data.read_u32_le()?,
+            }),
+            CommandNumber::GetBatteryLevel => Ok(Self::BatteryLevel {
+                level: data.read_u16_le()?,
+            }),
+            CommandNumber::GetHelloHarvard => {
+                let flag = |offset| {
+                    packet
+                        .data
+                        .get(offset)
+                        .map(|&b| b != 0)
+                        .ok_or(WhoopError::InvalidIndexError)
+                };
+
+                Ok(Self::HelloHarvard {
+                    charging: flag(Self::CHARGING_OFFSET)?,
+                    is_worn: flag(Self::IS_WORN_OFFSET)?,
+                })
+            }
+            CommandNumber::GetAdvertisingNameHarvard => {
+                let len = data.pop_front()? as usize;
+                let name = data.get(..len).ok_or(WhoopError::InvalidIndexError)?;
+                let name = String::from_utf8(name.to_vec()).map_err(|_| WhoopError::InvalidData)?;
+                Ok(Self::AdvertisingName {
+                    name: name.trim_end_matches('\0').to_owned(),
+                })
+            }
+            CommandNumber::ReportVersionInfo => {
+                let _ = data.pop_front()?;
+                let mut version = || -> Result<String, WhoopError> {
+                    let parts = [
+                        data.read_u32_le()?,
+                        data.read_u32_le()?,
+                        data.read_u32_le()?,
+                        data.read_u32_le()?,
+                    ];
+                    Ok(parts.map(|p| p.to_string()).join("."))
+                };
+
+                Ok(Self::VersionInfo {
+                    harvard: version()?,
+                    boylston: version()?,
+                })
+            }
+            CommandNumber::GetDataRange => Ok(Self::DataRange {
+                start: data.read_u32_le()?,
+                end: data.read_u32_le()?,
+            }),
+            _ => Err(WhoopError::Unimplemented),
+        }
+    }
+}
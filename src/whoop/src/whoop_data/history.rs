@@ -1,11 +1,179 @@
 use chrono::NaiveDateTime;
 
+use crate::{helpers::BufferReader, WhoopError, WhoopPacket};
+
+use super::HistoryDecoders;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
 pub struct HistoryReading {
     pub unix: u32,
     pub bpm: u8,
     pub rr: Vec<u16>,
     pub activity: u32,
+    /// Skin temperature in 1/128 °C, only present in longer records
+    pub skin_temp_raw: Option<u16>,
+}
+
+impl HistoryReading {
+    /// Skin temperature in °C
+    pub fn skin_temp(&self) -> Option<f64> {
+        self.skin_temp_raw.map(|raw| f64::from(raw) / 128.0)
+    }
+}
+
+/// Every field of historical record, [`HistoryReading`] is the subset of it used for analysis
+///
+/// Observed layout of packet data (offsets from start of data):
+/// - `[0..4]` `sequence: u32`, increments by one for every stored record
+/// - `[4..8]` `unix: u32`
+/// - `[8..14]` `unknown_header`, so far second half is always `80 54 54 01`
+/// - `[14]` `bpm: u8`
+/// - `[15]` number of RR intervals
+/// - `[16..24]` `rr: [u16; 4]`, unused slots are zero
+/// - `[24..28]` `activity: u32`
+/// - `[28..]` [`HistoryRecordExtension`], if record is long enough
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryReadingV2 {
+    pub sequence: u32,
+    pub unix: u32,
+    pub unknown_header: [u8; 6],
+    pub bpm: u8,
+    pub rr: Vec<u16>,
+    pub activity: u32,
+    pub extension: Option<HistoryRecordExtension>,
+}
+
+/// Part of historical record after `activity`
+///
+/// Observed layout of packet data (offsets from start of data):
+/// - `[28]` `unknown_flags: u8`
+/// - `[29..33]` `unknown_scalar_a: f32`
+/// - `[33..45]` `gravity: [f32; 3]`, accelerometer direction, its magnitude is close to 1 g
+/// - `[45..49]` `unknown_scalar_b: f32`
+/// - `[49..61]` `gravity_repeat: [f32; 3]`, so far always same as `gravity`
+/// - `[61..73]` `series: [u16; 6]`
+/// - `[73..]` `tail`, in longer records `[84]` is `0x01` followed by skin temperature `u16`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryRecordExtension {
+    pub unknown_flags: u8,
+    pub unknown_scalar_a: f32,
+    pub gravity: [f32; 3],
+    pub unknown_scalar_b: f32,
+    pub gravity_repeat: [f32; 3],
+    pub series: [u16; 6],
+    pub tail: Vec<u8>,
+}
+
+impl HistoryReadingV2 {
+    /// Parses record with decoder registered for its version in [`HistoryDecoders::known`]
+    pub fn from_packet(packet: &WhoopPacket) -> Result<Self, WhoopError> {
+        HistoryDecoders::known().decode(packet)
+    }
+
+    /// Layout of record version 5
+    pub(crate) fn parse(mut data: &[u8]) -> Result<Self, WhoopError> {
+        let sequence = data.read_u32_le()?;
+        let unix = data.read_u32_le()?;
+        let unknown_header = data.read::<6>()?;
+        let bpm = data.pop_front()?;
+        let rr_count = data.pop_front()?;
+        let mut rr = Vec::new();
+        for _ in 0..4 {
+            let rr_ = data.read_u16_le()?;
+            if rr_ == 0 {
+                continue;
+            }
+            rr.push(rr_);
+        }
+        if rr.len() as u8 != rr_count {
+            return Err(WhoopError::InvalidData);
+        }
+
+        let activity = data.read_u32_le()?;
+        let extension = HistoryRecordExtension::parse(data).ok();
+
+        Ok(Self {
+            sequence,
+            unix,
+            unknown_header,
+            bpm,
+            rr,
+            activity,
+            extension,
+        })
+    }
+
+    /// Skin temperature in 1/128 °C, only present in longer records
+    pub fn skin_temp_raw(&self) -> Option<u16> {
+        self.extension.as_ref()?.skin_temp_raw()
+    }
+}
+
+impl HistoryRecordExtension {
+    /// Offset of skin temperature flag in `tail`, `[84]` of packet data
+    const SKIN_TEMP_OFFSET: usize = 11;
+
+    fn parse(mut data: &[u8]) -> Result<Self, WhoopError> {
+        let f32x3 = |data: &mut &[u8]| -> Result<[f32; 3], WhoopError> {
+            Ok([
+                data.read_f32_le()?,
+                data.read_f32_le()?,
+                data.read_f32_le()?,
+            ])
+        };
+
+        let unknown_flags = data.pop_front()?;
+        let unknown_scalar_a = data.read_f32_le()?;
+        let gravity = f32x3(&mut data)?;
+        let unknown_scalar_b = data.read_f32_le()?;
+        let gravity_repeat = f32x3(&mut data)?;
+        let mut series = [0; 6];
+        for value in series.iter_mut() {
+            *value = data.read_u16_le()?;
+        }
+
+        Ok(Self {
+            unknown_flags,
+            unknown_scalar_a,
+            gravity,
+            unknown_scalar_b,
+            gravity_repeat,
+            series,
+            tail: data.to_vec(),
+        })
+    }
+
+    pub fn skin_temp_raw(&self) -> Option<u16> {
+        match self
+            .tail
+            .get(Self::SKIN_TEMP_OFFSET..Self::SKIN_TEMP_OFFSET + 3)
+        {
+            Some(&[1, low, high]) => Some(u16::from_le_bytes([low, high])),
+            _ => None,
+        }
+    }
+}
+
+impl From<HistoryReadingV2> for HistoryReading {
+    fn from(value: HistoryReadingV2) -> Self {
+        Self {
+            unix: value.unix,
+            bpm: value.bpm,
+            skin_temp_raw: value.skin_temp_raw(),
+            rr: value.rr,
+            activity: value.activity,
+        }
+    }
+}
+
+/// Heart rate sent by strap while realtime mode is enabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+pub struct RealtimeHeartRate {
+    pub unix: u32,
+    pub bpm: u8,
+    pub rr: Vec<u16>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -0,0 +1,69 @@
+use std::sync::OnceLock;
+
+use crate::{constants::PacketType, WhoopError, WhoopPacket};
+
+use super::HistoryReadingV2;
+
+/// Parses data of historical record with one layout version
+pub type HistoryDecoder = fn(&[u8]) -> Result<HistoryReadingV2, WhoopError>;
+
+/// Decoders of historical records keyed by record version.
+///
+/// Version is the `cmd` byte of `HISTORICAL_DATA` packet, record length and layout change between
+/// firmware versions together with it. New layouts are added with [`HistoryDecoders::register`]
+#[derive(Debug, Clone)]
+pub struct HistoryDecoders {
+    decoders: Vec<(u8, HistoryDecoder)>,
+}
+
+impl Default for HistoryDecoders {
+    /// Versions with known layout
+    fn default() -> Self {
+        Self::empty().register(5, HistoryReadingV2::parse)
+    }
+}
+
+impl HistoryDecoders {
+    /// Same decoders as [`HistoryDecoders::default`], built once and shared by parsers that
+    /// aren't given their own
+    pub fn known() -> &'static Self {
+        static KNOWN: OnceLock<HistoryDecoders> = OnceLock::new();
+        KNOWN.get_or_init(Self::default)
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            decoders: Vec::new(),
+        }
+    }
+
+    /// Adds decoder for `version`, replacing one that was already registered for it
+    pub fn register(mut self, version: u8, decoder: HistoryDecoder) -> Self {
+        self.decoders.retain(|(v, _)| *v != version);
+        self.decoders.push((version, decoder));
+        self
+    }
+
+    pub fn versions(&self) -> impl Iterator<Item = u8> + '_ {
+        self.decoders.iter().map(|(version, _)| *version)
+    }
+
+    pub fn get(&self, version: u8) -> Option<HistoryDecoder> {
+        self.decoders
+            .iter()
+            .find(|(v, _)| *v == version)
+            .map(|(_, decoder)| *decoder)
+    }
+
+    pub fn decode(&self, packet: &WhoopPacket) -> Result<HistoryReadingV2, WhoopError> {
+        if packet.packet_type != PacketType::HistoricalData {
+            return Err(WhoopError::InvalidPacketType(packet.packet_type.as_u8()));
+        }
+
+        let decoder = self
+            .get(packet.cmd)
+            .ok_or(WhoopError::UnknownHistoryVersion(packet.cmd))?;
+
+        decoder(&packet.data)
+    }
+}
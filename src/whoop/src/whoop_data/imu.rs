@@ -0,0 +1,45 @@
+use crate::{helpers::BufferReader, WhoopError};
+
+/// Batch of IMU samples, all samples in batch share `unix` and are ordered as they were measured
+///
+/// Layout of packet data: `[unix: u32][?: u16][count: u16][count * sample]`,
+/// where each sample is 6 `i16` values: accelerometer x, y, z and gyroscope x, y, z
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+pub struct ImuData {
+    pub unix: u32,
+    pub samples: Vec<ImuSample>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+pub struct ImuSample {
+    pub accelerometer: [i16; 3],
+    pub gyroscope: [i16; 3],
+}
+
+impl ImuData {
+    pub(crate) fn parse(mut packet: &[u8]) -> Result<Self, WhoopError> {
+        let unix = packet.read_u32_le()?;
+        let _something = packet.read::<2>()?;
+        let count = packet.read_u16_le()?;
+
+        let mut samples = Vec::with_capacity(count.into());
+        for _ in 0..count {
+            let mut axes = || -> Result<[i16; 3], WhoopError> {
+                Ok([
+                    packet.read_i16_le()?,
+                    packet.read_i16_le()?,
+                    packet.read_i16_le()?,
+                ])
+            };
+
+            samples.push(ImuSample {
+                accelerometer: axes()?,
+                gyroscope: axes()?,
+            });
+        }
+
+        Ok(Self { unix, samples })
+    }
+}
@@ -0,0 +1,57 @@
+use chrono::NaiveDateTime;
+
+use crate::{helpers::BufferReader, WhoopError};
+
+/// Batch of raw optical (PPG) samples sent while raw data collection is running
+///
+/// Layout of packet data: `[unix: u32][sample_rate: u16][count: u16][count * sample]`,
+/// where each sample is 4 `u32` ADC values: green, red, infrared and ambient channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+pub struct RawOpticalData {
+    pub unix: u32,
+    /// Samples per second
+    pub sample_rate: u16,
+    pub samples: Vec<PpgSample>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(serde::Serialize))]
+pub struct PpgSample {
+    pub green: u32,
+    pub red: u32,
+    pub infrared: u32,
+    pub ambient: u32,
+}
+
+/// [`PpgSample`] with its own time, reconstructed from batch time, sample position and sample rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParsedPpgSample {
+    pub time: NaiveDateTime,
+    pub sample_rate: f64,
+    pub sample: PpgSample,
+}
+
+impl RawOpticalData {
+    pub(crate) fn parse(mut packet: &[u8]) -> Result<Self, WhoopError> {
+        let unix = packet.read_u32_le()?;
+        let sample_rate = packet.read_u16_le()?;
+        let count = packet.read_u16_le()?;
+
+        let mut samples = Vec::with_capacity(count.into());
+        for _ in 0..count {
+            samples.push(PpgSample {
+                green: packet.read_u32_le()?,
+                red: packet.read_u32_le()?,
+                infrared: packet.read_u32_le()?,
+                ambient: packet.read_u32_le()?,
+            });
+        }
+
+        Ok(Self {
+            unix,
+            sample_rate,
+            samples,
+        })
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    constants::{EventNumber, PacketType},
+    helpers::BufferReader,
+    WhoopError, WhoopPacket,
+};
+
+/// Event sent by strap on `EVENTS_FROM_STRAP`, packet `cmd` is an [`EventNumber`]
+///
+/// Layout of packet data: `[?: u8][unix: u32][?: u16][payload..]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StrapEvent {
+    WristOn {
+        unix: u32,
+    },
+    WristOff {
+        unix: u32,
+    },
+    ChargingOn {
+        unix: u32,
+    },
+    ChargingOff {
+        unix: u32,
+    },
+    /// Battery level in tenths of percent
+    BatteryLevel {
+        unix: u32,
+        level: u16,
+    },
+    TemperatureLevel {
+        unix: u32,
+        raw: u16,
+    },
+    DoubleTap {
+        unix: u32,
+    },
+    Boot {
+        unix: u32,
+    },
+    RtcLost {
+        unix: u32,
+    },
+    SetRtc {
+        unix: u32,
+    },
+    BleConnectionUp {
+        unix: u32,
+    },
+    BleConnectionDown {
+        unix: u32,
+    },
+    HapticsFired {
+        unix: u32,
+    },
+    TrimAllData {
+        unix: u32,
+    },
+    /// Known event without typed payload
+    Other {
+        unix: u32,
+        event: EventNumber,
+        payload: Vec<u8>,
+    },
+    Unknown {
+        unix: u32,
+        event: u8,
+        payload: Vec<u8>,
+    },
+}
+
+impl StrapEvent {
+    pub fn from_packet(packet: &WhoopPacket) -> Result<Self, WhoopError> {
+        if packet.packet_type != PacketType::Event {
+            return Err(WhoopError::InvalidPacketType(packet.packet_type.as_u8()));
+        }
+
+        let mut data = packet.data.as_slice();
+        let _ = data.pop_front()?;
+        let unix = data.read_u32_le()?;
+        let _ = data.read::<2>()?;
+        let payload = data;
+
+        let Some(event) = EventNumber::from_u8(packet.cmd) else {
+            return Ok(Self::Unknown {
+                unix,
+                event: packet.cmd,
+                payload: payload.to_vec(),
+            });
+        };
+
+        let event = match event {
+            EventNumber::WristOn => Self::WristOn { unix },
+            EventNumber::WristOff => Self::WristOff { unix },
+            EventNumber::ChargingOn => Self::ChargingOn { unix },
+            EventNumber::ChargingOff => Self::ChargingOff { unix },
+            EventNumber::BatteryLevel => Self::BatteryLevel {
+                unix,
+                level: data.read_u16_le()?,
+            },
+            EventNumber::TemperatureLevel => Self::TemperatureLevel {
+                unix,
+                raw: data.read_u16_le()?,
+            },
+            EventNumber::DoubleTap => Self::DoubleTap { unix },
+            EventNumber::Boot => Self::Boot { unix },
+            EventNumber::RtcLost => Self::RtcLost { unix },
+            EventNumber::SetRtc => Self::SetRtc { unix },
+            EventNumber::BleConnectionUp => Self::BleConnectionUp { unix },
+            EventNumber::BleConnectionDown => Self::BleConnectionDown { unix },
+            EventNumber::HapticsFired => Self::HapticsFired { unix },
+            EventNumber::TrimAllData => Self::TrimAllData { unix },
+            event => Self::Other {
+                unix,
+                event,
+                payload: payload.to_vec(),
+            },
+        };
+
+        Ok(event)
+    }
+
+    pub fn unix(&self) -> u32 {
+        match self {
+            Self::WristOn { unix }
+            | Self::WristOff { unix }
+            | Self::ChargingOn { unix }
+            | Self::ChargingOff { unix }
+            | Self::BatteryLevel { unix, .. }
+            | Self::TemperatureLevel { unix, .. }
+            | Self::DoubleTap { unix }
+            | Self::Boot { unix }
+            | Self::RtcLost { unix }
+            | Self::SetRtc { unix }
+            | Self::BleConnectionUp { unix }
+            | Self::BleConnectionDown { unix }
+            | Self::HapticsFired { unix }
+            | Self::TrimAllData { unix }
+            | Self::Other { unix, .. }
+            | Self::Unknown { unix, .. } => *unix,
+        }
+    }
+
+    /// Raw event number, it is the `cmd` of packet this event was parsed from
+    pub fn event_number(&self) -> u8 {
+        let event = match self {
+            Self::WristOn { .. } => EventNumber::WristOn,
+            Self::WristOff { .. } => EventNumber::WristOff,
+            Self::ChargingOn { .. } => EventNumber::ChargingOn,
+            Self::ChargingOff { .. } => EventNumber::ChargingOff,
+            Self::BatteryLevel { .. } => EventNumber::BatteryLevel,
+            Self::TemperatureLevel { .. } => EventNumber::TemperatureLevel,
+            Self::DoubleTap { .. } => EventNumber::DoubleTap,
+            Self::Boot { .. } => EventNumber::Boot,
+            Self::RtcLost { .. } => EventNumber::RtcLost,
+            Self::SetRtc { .. } => EventNumber::SetRtc,
+            Self::BleConnectionUp { .. } => EventNumber::BleConnectionUp,
+            Self::BleConnectionDown { .. } => EventNumber::BleConnectionDown,
+            Self::HapticsFired { .. } => EventNumber::HapticsFired,
+            Self::TrimAllData { .. } => EventNumber::TrimAllData,
+            Self::Other { event, .. } => *event,
+            Self::Unknown { event, .. } => return *event,
+        };
+
+        event.as_u8()
+    }
+
+    /// Human readable name of the event
+    pub fn name(&self) -> String {
+        match EventNumber::from_u8(self.event_number()) {
+            Some(event) => format!("{:?}", event),
+            None => format!("Unknown({})", self.event_number()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::{EventNumber, PacketType};
+
+    use super::*;
+
+    fn event(event: u8, data: &str) -> StrapEvent {
+        let packet = WhoopPacket::new(
+            PacketType::Event,
+            0,
+            event,
+            hex::decode(data).expect("Invalid hex data"),
+        );
+        StrapEvent::from_packet(&packet).expect("Invalid event")
+    }
+
+    #[test]
+    fn parse_strap_events() {
+        let wrist_on = event(EventNumber::WristOn.as_u8(), "00b70c54670000");
+        assert_eq!(wrist_on, StrapEvent::WristOn { unix: 1733561527 });
+        assert_eq!(wrist_on.name(), "WristOn");
+
+        assert_eq!(
+            event(EventNumber::BatteryLevel.as_u8(), "00b70c546700005203"),
+            StrapEvent::BatteryLevel {
+                unix: 1733561527,
+                level: 850
+            }
+        );
+
+        assert_eq!(
+            event(EventNumber::HighFreqSyncEnabled.as_u8(), "00b70c5467000001"),
+            StrapEvent::Other {
+                unix: 1733561527,
+                event: EventNumber::HighFreqSyncEnabled,
+                payload: vec![0x01]
+            }
+        );
+
+        let unknown = event(200, "00b70c5467000001");
+        assert_eq!(unknown.event_number(), 200);
+        assert_eq!(unknown.name(), "Unknown(200)");
+    }
+}